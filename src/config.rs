@@ -1,21 +1,88 @@
 use std::env;
+use std::str::FromStr;
 use std::sync::LazyLock;
 
 use alloy::providers::RootProvider;
-use alloy::transports::http::Http;
-use reqwest::Client;
+use serde::Deserialize;
 use sqlx::{Pool, Postgres};
 
+use crate::utils::{circuit_breaker::CircuitBreaker, rpc_pool::RpcPool};
+
 pub fn load_config() {
     // load envs
     dotenv::dotenv().ok();
 }
 
+// every configured chain is served with its own state, sharing the db connection
 #[derive(Clone, Debug)]
 pub struct AppState {
-    pub eth_client: RootProvider<Http<Client>>,
+    pub chain: ChainConfig,
+    pub eth_client: RootProvider<RpcPool>, // sends every call through the rpc_pool
+    pub rpc_pool: RpcPool,
+    pub circuit_breaker: CircuitBreaker, // guards the trx fetches of utils/chain against a failing node
     pub db_connection: Pool<Postgres>,
 }
+impl AppState {
+    // the chain id stored with every cached row
+    pub fn chain_id(&self) -> i64 {
+        self.chain.chain_id as i64
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ChainConfig {
+    #[serde(rename = "chainId")]
+    pub chain_id: u64, // checked against the eth_chainId of a node when connecting it
+    pub name: String, // the route prefix of the chain, e.g. /lime/sepolia/eth
+    #[serde(rename = "rpcUrls")]
+    pub rpc_endpoints: Vec<RpcEndpointConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(from = "RpcEndpointEntry")]
+pub struct RpcEndpointConfig {
+    pub url: String,
+    pub priority: u32, // lower priorities are preferred, endpoints of a priority share the load
+    pub weight: u32,   // the share of the load within the priority
+}
+
+// an rpc url is either a plain url or an object with its priority and weight
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RpcEndpointEntry {
+    Url(String),
+    Endpoint {
+        url: String,
+        #[serde(default)]
+        priority: u32,
+        #[serde(default = "default_rpc_weight")]
+        weight: u32,
+    },
+}
+impl From<RpcEndpointEntry> for RpcEndpointConfig {
+    fn from(entry: RpcEndpointEntry) -> RpcEndpointConfig {
+        match entry {
+            RpcEndpointEntry::Url(url) => RpcEndpointConfig {
+                url,
+                priority: 0,
+                weight: default_rpc_weight(),
+            },
+            RpcEndpointEntry::Endpoint {
+                url,
+                priority,
+                weight,
+            } => RpcEndpointConfig {
+                url,
+                priority,
+                weight,
+            },
+        }
+    }
+}
+
+fn default_rpc_weight() -> u32 {
+    1
+}
 
 pub static API_PORT: LazyLock<String> =
     LazyLock::new(|| env::var("API_PORT").expect("API_PORT is not set"));
@@ -26,6 +93,288 @@ pub static ETH_NODE_URL: LazyLock<String> =
 pub static DB_CONNECTION_URL: LazyLock<String> =
     LazyLock::new(|| env::var("DB_CONNECTION_URL").expect("DB_CONNECTION_URL is not set"));
 
+// optional envs fall back to their default when missing or invalid
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+// like env_or, a zero falls back to the default as nothing would ever be fetched with it
+fn env_or_non_zero(name: &str, default: usize) -> usize {
+    match env_or(name, default) {
+        0 => {
+            println!("{} must not be 0, using {}", name, default);
+            default
+        }
+        value => value,
+    }
+}
+
+// max number of transaction hashes resolved at the same time per request
+pub static FETCH_CONCURRENCY: LazyLock<usize> =
+    LazyLock::new(|| env_or_non_zero("FETCH_CONCURRENCY", 10));
+
+// max number of transaction hashes fetched from chain in a single JSON-RPC batch
+pub static RPC_BATCH_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_or_non_zero("RPC_BATCH_SIZE", 50));
+
+// number of blocks below the chain head checked for reorgs
+pub static REORG_CHECK_DEPTH: LazyLock<u64> = LazyLock::new(|| env_or("REORG_CHECK_DEPTH", 64));
+
+// seconds between two reorg checks
+pub static REORG_CHECK_INTERVAL_SECS: LazyLock<u64> =
+    LazyLock::new(|| env_or("REORG_CHECK_INTERVAL_SECS", 12));
+
+// block tag ("finalized" or "safe") at or below which cached transactions are immutable
+pub static FINALITY_TAG: LazyLock<String> =
+    LazyLock::new(|| env_or("FINALITY_TAG", "finalized".to_string()));
+
+// seconds after which cached transactions above the FINALITY_TAG block are revalidated
+pub static CACHE_TTL_SECS: LazyLock<u64> = LazyLock::new(|| env_or("CACHE_TTL_SECS", 60));
+
+// when true, call traces are captured with debug_traceTransaction on request
+pub static TRACE_ENABLED: LazyLock<bool> = LazyLock::new(|| env_or("TRACE_ENABLED", false));
+
+// comma separated usernames allowed to call the admin endpoints
+pub static ADMIN_USERS: LazyLock<Vec<String>> = LazyLock::new(|| {
+    env_or("ADMIN_USERS", String::new())
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+});
+
+// the configured chains as a JSON list of {"chainId", "name", "rpcUrls"}, when missing
+// a single chain is built from ETH_NODE_URL, ETH_CHAIN_ID and ETH_CHAIN_NAME.
+// Comma separated ETH_NODE_URL urls are used in failover order, the first one is the primary.
+// The first chain is the default one, also served under the unprefixed /lime routes
+pub static CHAINS: LazyLock<Vec<ChainConfig>> = LazyLock::new(|| {
+    let chains: Vec<ChainConfig> = match env::var("CHAINS") {
+        Ok(chains) if !chains.trim().is_empty() => {
+            serde_json::from_str(&chains).expect("CHAINS is not a valid list of chains")
+        }
+        _ => vec![ChainConfig {
+            chain_id: env_or("ETH_CHAIN_ID", 11155111),
+            name: env_or("ETH_CHAIN_NAME", "sepolia".to_string()),
+            rpc_endpoints: ETH_NODE_URL
+                .split(',')
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+                .enumerate()
+                .map(|(priority, url)| RpcEndpointConfig {
+                    url,
+                    priority: priority as u32,
+                    weight: default_rpc_weight(),
+                })
+                .collect(),
+        }],
+    };
+
+    if let Err(error) = check_chains(&chains) {
+        panic!("Invalid CHAINS: {}", error);
+    }
+
+    chains
+});
+
+// path segments under /lime that are routes themselves and can not name a chain
+const RESERVED_CHAIN_NAMES: [&str; 13] = [
+    "eth",
+    "all",
+    "block",
+    "address",
+    "reorgs",
+    "abi",
+    "signatures",
+    "rpc",
+    "watchlist",
+    "webhooks",
+    "metrics",
+    "authenticate",
+    "my",
+];
+
+// every chain is served under /lime/:name and /lime/:chainId, so the names and ids must be
+// unique, must not shadow a route and a name must not be the id of another chain
+fn check_chains(chains: &[ChainConfig]) -> Result<(), String> {
+    if chains.is_empty() {
+        return Err("CHAINS must contain at least one chain".to_string());
+    }
+
+    for (index, chain) in chains.iter().enumerate() {
+        if chain.name.is_empty() || chain.name.contains('/') {
+            return Err(format!(
+                "Chain name \"{}\" is not a single path segment",
+                chain.name
+            ));
+        }
+        if RESERVED_CHAIN_NAMES.contains(&chain.name.as_str()) {
+            return Err(format!(
+                "Chain name \"{}\" is reserved for the /lime/{} routes",
+                chain.name, chain.name
+            ));
+        }
+        if chain.rpc_endpoints.is_empty() {
+            return Err(format!("Chain {} has no rpc urls", chain.name));
+        }
+        if chains[..index]
+            .iter()
+            .any(|other| other.name == chain.name || other.chain_id == chain.chain_id)
+        {
+            return Err(format!(
+                "Chain {} ({}) is configured more than once",
+                chain.name, chain.chain_id
+            ));
+        }
+        if let Some(other) = chains
+            .iter()
+            .find(|other| other.chain_id.to_string() == chain.name)
+        {
+            return Err(format!(
+                "Chain name \"{}\" is the chain id of chain {}",
+                chain.name, other.name
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// seconds between two health checks of the rpc endpoints
+pub static RPC_HEALTH_CHECK_INTERVAL_SECS: LazyLock<u64> =
+    LazyLock::new(|| env_or("RPC_HEALTH_CHECK_INTERVAL_SECS", 10));
+
+// blocks an rpc endpoint may lag behind the most advanced endpoint of its chain
+pub static RPC_MAX_BLOCK_LAG: LazyLock<u64> = LazyLock::new(|| env_or("RPC_MAX_BLOCK_LAG", 5));
+
+// share of failed calls within RPC_ERROR_WINDOW above which an rpc endpoint is unhealthy
+pub static RPC_MAX_ERROR_RATE: LazyLock<f64> = LazyLock::new(|| env_or("RPC_MAX_ERROR_RATE", 0.5));
+
+// number of recent calls of an rpc endpoint its error rate is computed over
+pub static RPC_ERROR_WINDOW: LazyLock<usize> = LazyLock::new(|| env_or("RPC_ERROR_WINDOW", 20));
+
+// milliseconds a single call to an rpc endpoint may take before it times out
+pub static RPC_CALL_TIMEOUT_MS: LazyLock<u64> =
+    LazyLock::new(|| env_or("RPC_CALL_TIMEOUT_MS", 10000));
+
+// milliseconds a request may wait on the node, the trxs not resolved by then are timed out
+pub static REQUEST_DEADLINE_MS: LazyLock<u64> =
+    LazyLock::new(|| env_or("REQUEST_DEADLINE_MS", 30000));
+
+// retries of an rpc call after all endpoints answered with a rate limit
+pub static RPC_RATE_LIMIT_RETRIES: LazyLock<u32> =
+    LazyLock::new(|| env_or("RPC_RATE_LIMIT_RETRIES", 5));
+
+// retries of an rpc call after all endpoints timed out
+pub static RPC_TIMEOUT_RETRIES: LazyLock<u32> = LazyLock::new(|| env_or("RPC_TIMEOUT_RETRIES", 2));
+
+// retries of an rpc call after all endpoints failed with a node error
+pub static RPC_NODE_ERROR_RETRIES: LazyLock<u32> =
+    LazyLock::new(|| env_or("RPC_NODE_ERROR_RETRIES", 3));
+
+// delay before the first retry, doubled on every further retry
+pub static RPC_RETRY_BASE_DELAY_MS: LazyLock<u64> =
+    LazyLock::new(|| env_or("RPC_RETRY_BASE_DELAY_MS", 250));
+
+// upper bound of the retry delay, also of a longer Retry-After asked for by the node
+pub static RPC_RETRY_MAX_DELAY_MS: LazyLock<u64> =
+    LazyLock::new(|| env_or("RPC_RETRY_MAX_DELAY_MS", 10000));
+
+// consecutive failed node calls after which the circuit breaker opens
+pub static CIRCUIT_BREAKER_FAILURE_THRESHOLD: LazyLock<u32> =
+    LazyLock::new(|| env_or("CIRCUIT_BREAKER_FAILURE_THRESHOLD", 5));
+
+// seconds the circuit breaker stays open before a probe call is let through
+pub static CIRCUIT_BREAKER_OPEN_SECS: LazyLock<u64> =
+    LazyLock::new(|| env_or("CIRCUIT_BREAKER_OPEN_SECS", 30));
+
+// when true, a background task saves the trxs of the watched addresses from every new block
+pub static INDEXER_ENABLED: LazyLock<bool> = LazyLock::new(|| env_or("INDEXER_ENABLED", true));
+
+// seconds between two checks for new blocks when no ws or ipc endpoint is configured
+pub static INDEXER_POLL_INTERVAL_SECS: LazyLock<u64> =
+    LazyLock::new(|| env_or("INDEXER_POLL_INTERVAL_SECS", 12));
+
+// max number of blocks scanned after a restart, older blocks behind the cursor are skipped
+pub static INDEXER_MAX_CATCH_UP_BLOCKS: LazyLock<u64> =
+    LazyLock::new(|| env_or("INDEXER_MAX_CATCH_UP_BLOCKS", 1000));
+
+// seconds between two checks of the tracked trxs for webhook events
+pub static WEBHOOK_CHECK_INTERVAL_SECS: LazyLock<u64> =
+    LazyLock::new(|| env_or("WEBHOOK_CHECK_INTERVAL_SECS", 12));
+
+// confirmations after which transaction.confirmed is sent, when not set on tracking
+pub static WEBHOOK_DEFAULT_CONFIRMATIONS: LazyLock<i32> =
+    LazyLock::new(|| env_or("WEBHOOK_DEFAULT_CONFIRMATIONS", 12));
+
+// seconds between two looks into the outbox for due webhook deliveries
+pub static WEBHOOK_DELIVERY_INTERVAL_SECS: LazyLock<u64> =
+    LazyLock::new(|| env_or("WEBHOOK_DELIVERY_INTERVAL_SECS", 5));
+
+// milliseconds a webhook url may take to answer a delivery
+pub static WEBHOOK_TIMEOUT_MS: LazyLock<u64> =
+    LazyLock::new(|| env_or("WEBHOOK_TIMEOUT_MS", 10000));
+
+// attempts after which a webhook delivery is given up on
+pub static WEBHOOK_MAX_ATTEMPTS: LazyLock<i32> =
+    LazyLock::new(|| env_or("WEBHOOK_MAX_ATTEMPTS", 8));
+
+// seconds before the first retry of a webhook delivery, doubled on every further retry
+pub static WEBHOOK_RETRY_BASE_DELAY_SECS: LazyLock<i64> =
+    LazyLock::new(|| env_or("WEBHOOK_RETRY_BASE_DELAY_SECS", 10));
+
+// upper bound of the webhook retry delay
+pub static WEBHOOK_RETRY_MAX_DELAY_SECS: LazyLock<i64> =
+    LazyLock::new(|| env_or("WEBHOOK_RETRY_MAX_DELAY_SECS", 3600));
+
+// seconds between two checks of a trx streamed by /eth/:hash/events
+pub static TRANSACTION_EVENTS_INTERVAL_SECS: LazyLock<u64> =
+    LazyLock::new(|| env_or("TRANSACTION_EVENTS_INTERVAL_SECS", 6));
+
 #[allow(dead_code)]
 pub static JWT_SECRET: LazyLock<String> =
     LazyLock::new(|| env::var("JWT_SECRET").expect("JWT_SECRET is not set"));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_or_non_zero() {
+        env::set_var("TEST_ENV_OR_NON_ZERO_ZERO", "0");
+        env::set_var("TEST_ENV_OR_NON_ZERO_SET", "4");
+
+        assert_eq!(env_or_non_zero("TEST_ENV_OR_NON_ZERO_ZERO", 10), 10);
+        assert_eq!(env_or_non_zero("TEST_ENV_OR_NON_ZERO_SET", 10), 4);
+        assert_eq!(env_or_non_zero("TEST_ENV_OR_NON_ZERO_MISSING", 10), 10);
+    }
+
+    #[test]
+    fn test_check_chains() {
+        let chain = |chain_id: u64, name: &str| ChainConfig {
+            chain_id,
+            name: name.to_string(),
+            rpc_endpoints: vec![RpcEndpointConfig {
+                url: "http://localhost:8545".to_string(),
+                priority: 0,
+                weight: default_rpc_weight(),
+            }],
+        };
+
+        assert!(check_chains(&[chain(1, "mainnet"), chain(11155111, "sepolia")]).is_ok());
+        assert!(check_chains(&[]).is_err());
+        assert!(check_chains(&[chain(1, "eth")]).is_err());
+        assert!(check_chains(&[chain(1, "metrics")]).is_err());
+        assert!(check_chains(&[chain(1, "main/net")]).is_err());
+        assert!(check_chains(&[chain(1, "mainnet"), chain(2, "mainnet")]).is_err());
+        assert!(check_chains(&[chain(1, "mainnet"), chain(1, "sepolia")]).is_err());
+        assert!(check_chains(&[chain(1, "mainnet"), chain(2, "1")]).is_err());
+        assert!(check_chains(&[ChainConfig {
+            rpc_endpoints: vec![],
+            ..chain(1, "mainnet")
+        }])
+        .is_err());
+    }
+}
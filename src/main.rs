@@ -2,36 +2,107 @@ mod config;
 mod routes;
 mod utils;
 
-use alloy::providers::ProviderBuilder;
-use axum::Router;
-use config::{load_config, AppState, API_PORT, DB_CONNECTION_URL, ETH_NODE_URL};
-use utils::db::set_up::init_db;
+use alloy::{providers::RootProvider, rpc::client::RpcClient};
+use axum::{middleware::map_response_with_state, Router};
+use config::{load_config, AppState, API_PORT, CHAINS, DB_CONNECTION_URL, INDEXER_ENABLED};
+use utils::{
+    circuit_breaker::{mark_cache_only, CircuitBreaker},
+    db::set_up::init_db,
+    indexer::run_indexer,
+    reorg::watch_reorgs,
+    rpc_pool::{watch_rpc_endpoints, RpcPool},
+    webhook::{run_webhook_deliveries, watch_tracked_transactions},
+};
+
+// the routes served for each configured chain
+fn chain_routes(state: AppState) -> Router {
+    Router::new()
+        .nest("/eth", routes::eth::routes())
+        .nest("/all", routes::all::routes())
+        .nest("/block", routes::block::routes())
+        .nest("/address", routes::address::routes())
+        .nest("/reorgs", routes::reorg::routes())
+        .nest("/abi", routes::abi::routes())
+        .nest("/signatures", routes::signature::routes())
+        .nest("/rpc", routes::rpc::routes())
+        .nest("/watchlist", routes::watchlist::routes())
+        .nest("/webhooks", routes::webhook::routes())
+        .merge(routes::auth::routes())
+        .layer(map_response_with_state(state.clone(), mark_cache_only))
+        .with_state(state)
+}
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
     // load envs
     load_config();
 
-    let shared_state: AppState = AppState {
-        eth_client: ProviderBuilder::new().on_http(ETH_NODE_URL.parse().unwrap()),
-        db_connection: sqlx::postgres::PgPoolOptions::new()
-            .max_connections(50)
-            .connect(&DB_CONNECTION_URL)
+    let db_connection = sqlx::postgres::PgPoolOptions::new()
+        .max_connections(50)
+        .connect(&DB_CONNECTION_URL)
+        .await
+        .unwrap();
+
+    // rows cached before multi-chain support belong to the default chain
+    init_db(&db_connection, CHAINS[0].chain_id as i64)
+        .await
+        .unwrap();
+
+    // build routes, every chain is served under /lime/:name and /lime/:chainId
+    let mut app = Router::new();
+    let mut states: Vec<AppState> = vec![];
+    for (index, chain) in CHAINS.iter().enumerate() {
+        // unreachable endpoints are connected later by watch_rpc_endpoints
+        let rpc_pool = RpcPool::connect(chain.chain_id, &chain.rpc_endpoints)
             .await
-            .unwrap(),
-    };
+            .unwrap_or_else(|err_msg| {
+                panic!("Failed to connect chain {}: {}", chain.name, err_msg)
+            });
+        let shared_state: AppState = AppState {
+            chain: chain.clone(),
+            eth_client: RootProvider::new(RpcClient::new(rpc_pool.clone(), false)),
+            rpc_pool,
+            circuit_breaker: CircuitBreaker::default(),
+            db_connection: db_connection.clone(),
+        };
+        states.push(shared_state.clone());
+
+        // invalidate cached trxs of reorged blocks in the background
+        tokio::spawn(watch_reorgs(shared_state.clone()));
+
+        // fail over from lagging or failing rpc endpoints
+        tokio::spawn(watch_rpc_endpoints(shared_state.clone()));
+
+        // save the trxs of watched addresses from every new block
+        if *INDEXER_ENABLED {
+            tokio::spawn(run_indexer(shared_state.clone()));
+        }
+
+        // enqueue the webhook events of the tracked trxs
+        tokio::spawn(watch_tracked_transactions(shared_state.clone()));
+
+        // the default chain is also served under the unprefixed /lime routes
+        if index == 0 {
+            app = app.nest("/lime", chain_routes(shared_state.clone()));
+        }
+        app = app
+            .nest(
+                &format!("/lime/{}", chain.name),
+                chain_routes(shared_state.clone()),
+            )
+            .nest(
+                &format!("/lime/{}", chain.chain_id),
+                chain_routes(shared_state),
+            );
+    }
 
-    init_db(&shared_state.db_connection).await.unwrap();
+    app = app.nest("/lime/metrics", routes::metrics::routes(states));
 
-    // build routes
-    let app = Router::new()
-        .nest("/lime/eth", routes::eth::routes())
-        .nest("/lime/all", routes::all::routes())
-        .nest("/lime/", routes::auth::routes())
-        .with_state(shared_state);
+    // send the webhook deliveries of all chains from the outbox
+    tokio::spawn(run_webhook_deliveries(db_connection.clone()));
 
     // listen for server
-    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", API_PORT.to_string()))
+    let listener = tokio::net::TcpListener::bind(format!("0.0.0.0:{}", *API_PORT))
         .await
         .unwrap();
 
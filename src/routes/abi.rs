@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use alloy::{json_abi::JsonAbi, primitives::Address};
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use reqwest::StatusCode;
+
+use crate::{
+    config::AppState,
+    utils::{
+        db::abi::{fetch_contract_abis, save_contract_abi},
+        structs::{
+            abi::{AbiPayload, ContractAbi},
+            auth::AuthUser,
+        },
+    },
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", post(upload_abi))
+        .route("/:address", get(fetch_abi))
+}
+
+pub async fn upload_abi(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<AbiPayload>,
+) -> Result<Json<ContractAbi>, StatusCode> {
+    let address = Address::from_str(&payload.address).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    // only valid JSON ABIs are saved, so decoding can rely on them
+    if serde_json::from_value::<JsonAbi>(payload.abi.clone()).is_err() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let contract_abi = ContractAbi {
+        address: address.to_string().to_lowercase(),
+        abi: sqlx::types::Json(payload.abi),
+        uploaded_by: user.db_user().name.clone(),
+    };
+
+    // only the uploader or an admin can replace a registered abi
+    match save_contract_abi(
+        &state.db_connection,
+        state.chain_id(),
+        &contract_abi,
+        user.is_admin(),
+    )
+    .await
+    {
+        Ok(true) => Ok(Json(contract_abi)),
+        Ok(false) => Err(StatusCode::FORBIDDEN),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+pub async fn fetch_abi(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> Result<Json<ContractAbi>, StatusCode> {
+    match fetch_contract_abis(
+        &state.db_connection,
+        state.chain_id(),
+        vec![address.to_lowercase()],
+    )
+    .await
+    {
+        Ok(mut abis) => abis.pop().map(Json).ok_or(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
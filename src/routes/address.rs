@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use reqwest::StatusCode;
+
+use crate::{
+    config::AppState,
+    utils::{
+        address::{decode_cursor, encode_cursor, normalize_address},
+        db::transaction::fetch_address_transactions,
+        structs::address::{AddressTransactionsQuery, AddressTransactionsResponse},
+        transaction::{enrich_transactions, request_deadline},
+    },
+};
+
+const MAX_ADDRESS_TRANSACTIONS_LIMIT: i64 = 500;
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/:address/transactions", get(fetch_address_trxs))
+}
+
+pub async fn fetch_address_trxs(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+    Query(query): Query<AddressTransactionsQuery>,
+) -> Result<Json<AddressTransactionsResponse>, StatusCode> {
+    let address = normalize_address(&address).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if !(1..=MAX_ADDRESS_TRANSACTIONS_LIMIT).contains(&query.limit) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let cursor = query
+        .cursor
+        .as_deref()
+        .map(decode_cursor)
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    // one extra trx is fetched to know if there is a next page
+    let mut transactions = fetch_address_transactions(
+        &state.db_connection,
+        state.chain_id(),
+        &address,
+        query.direction,
+        cursor,
+        query.limit + 1,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let next_cursor = if transactions.len() as i64 > query.limit {
+        transactions.truncate(query.limit as usize);
+        transactions.last().map(encode_cursor)
+    } else {
+        None
+    };
+
+    enrich_transactions(&state, &mut transactions, false, request_deadline()).await;
+
+    Ok(Json(AddressTransactionsResponse {
+        transactions,
+        next_cursor,
+    }))
+}
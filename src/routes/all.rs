@@ -1,18 +1,35 @@
-use axum::{extract::State, routing::get, Json, Router};
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use reqwest::StatusCode;
 
 use crate::{
     config::AppState,
-    utils::{db::transaction::fetch_all_transactions, structs::transaction::FetchResponse},
+    utils::{
+        db::transaction::fetch_all_transactions,
+        structs::transaction::{DateRangeQuery, FetchResponse},
+    },
 };
 
 pub fn routes() -> Router<AppState> {
     Router::new().route("/", get(fetch_all_saved_trx))
 }
 
-pub async fn fetch_all_saved_trx(State(state): State<AppState>) -> Json<FetchResponse> {
-    let result = fetch_all_transactions(&state.db_connection).await.unwrap();
+pub async fn fetch_all_saved_trx(
+    State(state): State<AppState>,
+    Query(query): Query<DateRangeQuery>,
+) -> Result<Json<FetchResponse>, StatusCode> {
+    let (from, to) = query.unix_range().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let result = fetch_all_transactions(&state.db_connection, state.chain_id(), from, to)
+        .await
+        .unwrap();
 
-    Json(FetchResponse {
+    Ok(Json(FetchResponse {
         transactions: result,
-    })
+        errors: vec![],
+        cache_only: false,
+    }))
 }
@@ -1,5 +1,5 @@
 use axum::{
-    extract::State,
+    extract::{Query, State},
     routing::{get, post},
     Json, Router,
 };
@@ -12,7 +12,7 @@ use crate::{
         db::{transaction::fetch_matching_transactions, user::login_user},
         structs::{
             auth::{AuthPayload, AuthResponse, AuthUser},
-            transaction::FetchResponse,
+            transaction::{DateRangeQuery, FetchResponse},
         },
     },
 };
@@ -35,12 +35,25 @@ pub async fn authenticate(
 
 pub async fn my(
     State(state): State<AppState>,
+    Query(query): Query<DateRangeQuery>,
     user: AuthUser,
 ) -> Result<Json<FetchResponse>, StatusCode> {
-    match fetch_matching_transactions(&state.db_connection, user.db_user().transactions.clone())
-        .await
+    let (from, to) = query.unix_range().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    match fetch_matching_transactions(
+        &state.db_connection,
+        state.chain_id(),
+        user.db_user().transactions.clone(),
+        from,
+        to,
+    )
+    .await
     {
-        Ok(transactions) => Ok(Json(FetchResponse { transactions })),
+        Ok(transactions) => Ok(Json(FetchResponse {
+            transactions,
+            errors: vec![],
+            cache_only: false,
+        })),
         Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
     }
 }
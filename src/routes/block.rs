@@ -0,0 +1,74 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::get,
+    Json, Router,
+};
+use reqwest::StatusCode;
+
+use crate::{
+    config::AppState,
+    routes::eth::chain_error_status,
+    utils::{
+        block::{fetch_block, parse_block_id},
+        structs::{
+            block::{BlockQuery, BlockResponse},
+            chain::ChainError,
+            transaction::{FetchError, ResultTransaction},
+        },
+        transaction::{enrich_transactions, request_deadline, resolve_transactions},
+    },
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/:number_or_hash", get(fetch_eth_block))
+}
+
+pub async fn fetch_eth_block(
+    State(state): State<AppState>,
+    Path(number_or_hash): Path<String>,
+    Query(query): Query<BlockQuery>,
+) -> Result<Json<BlockResponse>, StatusCode> {
+    let block_id = parse_block_id(&number_or_hash).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let deadline = request_deadline();
+
+    let fetched = tokio::time::timeout_at(deadline, fetch_block(&state, block_id))
+        .await
+        .unwrap_or(Err(ChainError::DeadlineExceeded));
+    let block = match fetched {
+        Ok(block) => block,
+        Err(error) => {
+            println!("Failed to fetch block {}, error:{}", number_or_hash, error);
+            return Err(chain_error_status(&error));
+        }
+    };
+
+    if !query.expand {
+        return Ok(Json(BlockResponse {
+            block,
+            transactions: None,
+            errors: vec![],
+            cache_only: state.circuit_breaker.is_cache_only(),
+        }));
+    }
+
+    // the trxs of the block go through the same cache as /lime/eth
+    let mut transactions: Vec<ResultTransaction> = vec![];
+    let mut errors: Vec<FetchError> = vec![];
+    for (transaction_hash, trx) in
+        resolve_transactions(&state, block.transaction_hashes.clone(), deadline).await
+    {
+        match trx {
+            Ok(trx) => transactions.push(trx),
+            Err(error) => errors.push(FetchError::new(transaction_hash, &error)),
+        }
+    }
+
+    enrich_transactions(&state, &mut transactions, false, deadline).await;
+
+    Ok(Json(BlockResponse {
+        block,
+        transactions: Some(transactions),
+        errors,
+        cache_only: state.circuit_breaker.is_cache_only(),
+    }))
+}
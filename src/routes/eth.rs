@@ -1,20 +1,45 @@
+use std::convert::Infallible;
+
 use crate::{
+    config::{TRACE_ENABLED, WEBHOOK_DEFAULT_CONFIRMATIONS},
     utils::{
-        chain::fetch_from_chain,
-        db::{transaction::check_transaction_in_db, user::save_user_trx},
+        abi::decode_logs,
+        db::{
+            log::fetch_transaction_logs,
+            transaction::{check_transaction_in_cache, check_transaction_in_db},
+            transfer::fetch_transaction_transfers,
+            user::save_user_trx,
+        },
+        events::{stream_transaction_events, to_sse_event},
+        misc::parse_b256_from_str,
         rlp::decode_rlp_encoded_list,
+        signature::resolve_log_signatures,
         structs::{
             auth::AuthUser,
-            transaction::{FetchResponse, ResultTransaction, TransactionHashesQuery},
+            chain::{ChainError, RpcErrorKind},
+            events::TransactionEventsQuery,
+            log::LogsResponse,
+            trace::{TraceError, TraceResponse},
+            transaction::{
+                FetchError, FetchResponse, ResultTransaction, TransactionHashesQuery,
+                TransactionState,
+            },
+            transfer::TransfersResponse,
         },
+        trace::trace_transaction,
+        transaction::{enrich_transactions, fetch_until, request_deadline, resolve_transactions},
     },
     AppState,
 };
 use axum::{
     extract::{Path, Query, State},
+    response::sse::{Event, KeepAlive, Sse},
     routing::get,
     Json, Router,
 };
+use futures::{stream, Stream};
+use reqwest::StatusCode;
+use tokio::sync::mpsc;
 
 pub fn routes() -> Router<AppState> {
     Router::new()
@@ -28,6 +53,10 @@ pub fn routes() -> Router<AppState> {
                 fetch_eth_txs(app_state, query, Some(path), true, user)
             }),
         )
+        .route("/:hash/logs", get(fetch_eth_tx_logs))
+        .route("/:hash/transfers", get(fetch_eth_tx_transfers))
+        .route("/:hash/trace", get(fetch_eth_tx_trace))
+        .route("/:hash/events", get(stream_eth_tx_events))
 }
 
 pub async fn fetch_eth_txs(
@@ -37,7 +66,7 @@ pub async fn fetch_eth_txs(
     are_rlp_encoded: bool,
     user: Option<AuthUser>,
 ) -> Json<FetchResponse> {
-    let mut result: Vec<ResultTransaction> = vec![];
+    let include_logs = query.include_logs;
 
     let query_list = if are_rlp_encoded {
         match decode_rlp_encoded_list(&rlp.unwrap()) {
@@ -54,52 +83,221 @@ pub async fn fetch_eth_txs(
         query.transaction_hashes
     };
 
-    for i in 0..query_list.len() {
-        let transaction_hash = query_list.get(i).unwrap();
-
-        match check_transaction_in_db(&state.db_connection, transaction_hash).await {
-            Ok(Some(res)) => {
-                println!("{} fetched from db", transaction_hash);
-                result.push(res);
+    let mut result: Vec<ResultTransaction> = vec![];
+    let mut errors: Vec<FetchError> = vec![];
 
-                // save user trx if authenticated
+    let deadline = request_deadline();
+    for (transaction_hash, trx) in resolve_transactions(&state, query_list, deadline).await {
+        match trx {
+            Ok(trx) => {
+                // save user trx if authenticated and if trx exists, the trx is served either way
                 if let Some(auth_user) = &user {
-                    save_user_trx(
+                    if let Err(error) = save_user_trx(
                         &state.db_connection,
-                        transaction_hash,
+                        &transaction_hash,
                         &auth_user.db_user().name,
                     )
                     .await
-                    .expect("Failed to save user_trx");
+                    {
+                        println!(
+                            "Failed to save user_trx {}, error:{}",
+                            transaction_hash, error
+                        );
+                    }
                 };
+
+                result.push(trx);
+            }
+            Err(error) => {
+                println!(
+                    "Failed to fetch {} from chain, error:{}",
+                    transaction_hash, error
+                );
+                errors.push(FetchError::new(transaction_hash, &error));
             }
-            Ok(None) => match fetch_from_chain(transaction_hash, &state, &mut result).await {
-                Ok(_) => {
-                    println!("{} fetched and saved in db", transaction_hash);
-
-                    // save user trx if authenticated and if trx exists
-                    if let Some(auth_user) = &user {
-                        save_user_trx(
-                            &state.db_connection,
-                            transaction_hash,
-                            &auth_user.db_user().name,
-                        )
-                        .await
-                        .expect("Failed to save user_trx");
-                    };
-                }
-                Err(err_msg) => {
-                    println!(
-                        "Failed to fetch {} from chain, error:{}",
-                        transaction_hash, err_msg
-                    )
-                }
-            },
-            Err(error) => println!("Failed to fetch from db: {}", error),
         }
     }
 
+    enrich_transactions(&state, &mut result, include_logs, deadline).await;
+
     Json(FetchResponse {
         transactions: result,
+        errors,
+        cache_only: state.circuit_breaker.is_cache_only(),
     })
 }
+
+// an unknown trx is not found, a node that could not answer after all retries is unavailable
+pub fn chain_error_status(error: &ChainError) -> StatusCode {
+    match error {
+        ChainError::InvalidHash(_) | ChainError::NotFound(_) | ChainError::BlockNotFound(_) => {
+            StatusCode::NOT_FOUND
+        }
+        ChainError::Rpc {
+            kind: RpcErrorKind::Timeout,
+            ..
+        }
+        | ChainError::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+        ChainError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        ChainError::CircuitOpen => StatusCode::SERVICE_UNAVAILABLE,
+        ChainError::Rpc {
+            kind: RpcErrorKind::Rejected,
+            ..
+        } => StatusCode::BAD_GATEWAY,
+        ChainError::Rpc { .. } => StatusCode::SERVICE_UNAVAILABLE,
+    }
+}
+
+// the path hash in the lowercase form trxs are saved under, so a checksummed hash hits the cache
+fn normalize_transaction_hash(transaction_hash: &str) -> Result<String, StatusCode> {
+    parse_b256_from_str(transaction_hash)
+        .map(|hash| hash.to_string())
+        .map_err(|_| StatusCode::BAD_REQUEST)
+}
+
+// makes sure the trx and its logs are saved in the db, fetching it from chain when missing,
+// the hash is expected to be normalized
+async fn ensure_transaction_saved(
+    state: &AppState,
+    transaction_hash: &String,
+) -> Result<ResultTransaction, StatusCode> {
+    match check_transaction_in_db(&state.db_connection, state.chain_id(), transaction_hash).await {
+        Ok(Some(trx)) => {
+            println!("{} fetched from db", transaction_hash);
+            Ok(trx)
+        }
+        // fetching the trx from chain also saves its logs
+        Ok(None) => match fetch_until(state, transaction_hash, request_deadline()).await {
+            Ok(trx) => {
+                println!("{} fetched and saved in db", transaction_hash);
+                Ok(trx)
+            }
+            // while the node is unavailable an expired cached trx beats no trx
+            Err(ChainError::CircuitOpen) => match check_transaction_in_cache(
+                &state.db_connection,
+                state.chain_id(),
+                transaction_hash,
+                true,
+            )
+            .await
+            {
+                Ok(Some(trx)) => Ok(trx),
+                _ => Err(chain_error_status(&ChainError::CircuitOpen)),
+            },
+            Err(error) => {
+                println!(
+                    "Failed to fetch {} from chain, error:{}",
+                    transaction_hash, error
+                );
+                Err(chain_error_status(&error))
+            }
+        },
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+pub async fn fetch_eth_tx_logs(
+    State(state): State<AppState>,
+    Path(transaction_hash): Path<String>,
+) -> Result<Json<LogsResponse>, StatusCode> {
+    let transaction_hash = normalize_transaction_hash(&transaction_hash)?;
+    ensure_transaction_saved(&state, &transaction_hash).await?;
+
+    match fetch_transaction_logs(&state.db_connection, state.chain_id(), &transaction_hash).await {
+        Ok(mut logs) => {
+            decode_logs(&state, &mut logs).await;
+            resolve_log_signatures(&state, &mut logs).await;
+            Ok(Json(LogsResponse { logs }))
+        }
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+pub async fn fetch_eth_tx_transfers(
+    State(state): State<AppState>,
+    Path(transaction_hash): Path<String>,
+) -> Result<Json<TransfersResponse>, StatusCode> {
+    let transaction_hash = normalize_transaction_hash(&transaction_hash)?;
+    ensure_transaction_saved(&state, &transaction_hash).await?;
+
+    match fetch_transaction_transfers(&state.db_connection, state.chain_id(), &transaction_hash)
+        .await
+    {
+        Ok(transfers) => Ok(Json(TransfersResponse { transfers })),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+// the trace is opt-in and depends on the node's debug namespace,
+// when it can not be captured an empty trace is returned together with the reason
+pub async fn fetch_eth_tx_trace(
+    State(state): State<AppState>,
+    Path(transaction_hash): Path<String>,
+) -> Result<Json<TraceResponse>, StatusCode> {
+    let unavailable = |reason: String| {
+        Ok(Json(TraceResponse {
+            available: false,
+            reason: Some(reason),
+            calls: vec![],
+        }))
+    };
+
+    if !*TRACE_ENABLED {
+        return unavailable("Tracing is disabled, set TRACE_ENABLED=true".to_string());
+    }
+
+    let transaction_hash = normalize_transaction_hash(&transaction_hash)?;
+    let trx = ensure_transaction_saved(&state, &transaction_hash).await?;
+    if trx.state == TransactionState::Pending {
+        return unavailable("Pending transactions can not be traced".to_string());
+    }
+
+    match trace_transaction(&transaction_hash, &state).await {
+        Ok(calls) => Ok(Json(TraceResponse {
+            available: true,
+            reason: None,
+            calls,
+        })),
+        Err(TraceError::Unsupported(reason)) => {
+            println!("Failed to trace {}, error:{}", transaction_hash, reason);
+            unavailable(reason)
+        }
+        // the db and transport errors are not shown, they might contain internal details
+        Err(TraceError::Failed(error)) => {
+            println!("Failed to trace {}, error:{}", transaction_hash, error);
+            Err(chain_error_status(&error))
+        }
+    }
+}
+
+// streams the status changes of the trx as server-sent events, closing once it is finalized,
+// dropped or replaced
+pub async fn stream_eth_tx_events(
+    State(state): State<AppState>,
+    Path(transaction_hash): Path<String>,
+    Query(query): Query<TransactionEventsQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, StatusCode> {
+    let confirmations = query
+        .confirmations
+        .unwrap_or(*WEBHOOK_DEFAULT_CONFIRMATIONS as u64);
+
+    // an unknown trx is refused before the stream is opened
+    let trx = match resolve_transactions(&state, vec![transaction_hash], request_deadline())
+        .await
+        .pop()
+    {
+        Some((_, Ok(trx))) => trx,
+        Some((_, Err(error))) => return Err(chain_error_status(&error)),
+        None => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    let (sender, receiver) = mpsc::channel(16);
+    tokio::spawn(stream_transaction_events(state, trx, confirmations, sender));
+
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        let event = receiver.recv().await?;
+        Some((Ok(to_sse_event(&event)), receiver))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
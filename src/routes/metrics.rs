@@ -0,0 +1,24 @@
+use axum::{
+    extract::State, http::header::CONTENT_TYPE, response::IntoResponse, routing::get, Router,
+};
+
+use crate::{config::AppState, utils::metrics::render_metrics};
+
+// the metrics cover every chain, so they are served once instead of per chain
+pub fn routes(states: Vec<AppState>) -> Router {
+    Router::new()
+        .route("/", get(fetch_metrics))
+        .with_state(states)
+}
+
+pub async fn fetch_metrics(State(states): State<Vec<AppState>>) -> impl IntoResponse {
+    let breakers: Vec<_> = states
+        .iter()
+        .map(|state| (state.chain.name.clone(), state.circuit_breaker.status()))
+        .collect();
+
+    (
+        [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+        render_metrics(&breakers),
+    )
+}
@@ -1,3 +1,12 @@
+pub mod abi;
+pub mod address;
 pub mod all;
 pub mod auth;
+pub mod block;
 pub mod eth;
+pub mod metrics;
+pub mod reorg;
+pub mod rpc;
+pub mod signature;
+pub mod watchlist;
+pub mod webhook;
@@ -0,0 +1,28 @@
+use axum::{
+    extract::{Query, State},
+    routing::get,
+    Json, Router,
+};
+use reqwest::StatusCode;
+
+use crate::{
+    config::AppState,
+    utils::{
+        db::reorg::fetch_reorg_events,
+        structs::reorg::{ReorgEventsQuery, ReorgEventsResponse},
+    },
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/", get(fetch_recent_reorgs))
+}
+
+pub async fn fetch_recent_reorgs(
+    State(state): State<AppState>,
+    Query(query): Query<ReorgEventsQuery>,
+) -> Result<Json<ReorgEventsResponse>, StatusCode> {
+    match fetch_reorg_events(&state.db_connection, state.chain_id(), query.limit).await {
+        Ok(reorgs) => Ok(Json(ReorgEventsResponse { reorgs })),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -0,0 +1,20 @@
+use axum::{extract::State, routing::get, Json, Router};
+
+use crate::{
+    config::AppState,
+    utils::structs::{auth::AdminUser, rpc_pool::RpcEndpointsResponse},
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/endpoints", get(fetch_rpc_endpoints))
+}
+
+pub async fn fetch_rpc_endpoints(
+    State(state): State<AppState>,
+    _admin: AdminUser,
+) -> Json<RpcEndpointsResponse> {
+    Json(RpcEndpointsResponse {
+        endpoints: state.rpc_pool.status(),
+        circuit_breaker: state.circuit_breaker.status(),
+    })
+}
@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Query, State},
+    routing::post,
+    Json, Router,
+};
+use reqwest::StatusCode;
+
+use crate::{
+    config::AppState,
+    utils::{
+        db::signature::save_signatures,
+        signature::{parse_signature, parse_signature_entries},
+        structs::{
+            auth::AdminUser,
+            signature::{SignatureImportQuery, SignatureImportResponse, TextSignature},
+        },
+    },
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/", post(import_signatures))
+}
+
+pub async fn import_signatures(
+    State(state): State<AppState>,
+    Query(query): Query<SignatureImportQuery>,
+    admin: AdminUser,
+    body: String,
+) -> Result<Json<SignatureImportResponse>, StatusCode> {
+    let entries =
+        parse_signature_entries(&body, &query.format).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let mut signatures: Vec<TextSignature> = vec![];
+    let mut invalid: Vec<String> = vec![];
+    for entry in entries {
+        match entry.and_then(|entry| {
+            parse_signature(&entry).map_err(|_| format!("{},{}", entry.kind, entry.signature))
+        }) {
+            Ok(signature) => signatures.push(signature),
+            Err(line) => invalid.push(line),
+        }
+    }
+
+    match save_signatures(&state.db_connection, &signatures).await {
+        Ok(imported) => {
+            println!("{} imported {} signatures", admin.db_user().name, imported);
+            Ok(Json(SignatureImportResponse {
+                imported,
+                duplicates: signatures.len() as u64 - imported,
+                invalid,
+            }))
+        }
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Path, State},
+    routing::{delete, get},
+    Json, Router,
+};
+use reqwest::StatusCode;
+
+use crate::{
+    config::AppState,
+    utils::{
+        address::normalize_address,
+        db::watchlist::{delete_watched_address, fetch_user_watchlist, save_watched_address},
+        structs::{
+            auth::AuthUser,
+            watchlist::{WatchedAddress, WatchlistPayload, WatchlistResponse},
+        },
+    },
+};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(fetch_watchlist).post(watch_address))
+        .route("/:address", delete(unwatch_address))
+}
+
+pub async fn fetch_watchlist(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<WatchlistResponse>, StatusCode> {
+    match fetch_user_watchlist(&state.db_connection, state.chain_id(), &user.db_user().name).await {
+        Ok(addresses) => Ok(Json(WatchlistResponse { addresses })),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+// the trxs of watched addresses are saved by the indexer from the next indexed block on
+pub async fn watch_address(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<WatchlistPayload>,
+) -> Result<Json<WatchedAddress>, StatusCode> {
+    let address = normalize_address(&payload.address).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    match save_watched_address(
+        &state.db_connection,
+        state.chain_id(),
+        &user.db_user().name,
+        &address,
+        payload.label.as_ref(),
+    )
+    .await
+    {
+        Ok(watched_address) => Ok(Json(watched_address)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+pub async fn unwatch_address(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(address): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let address = normalize_address(&address).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    match delete_watched_address(
+        &state.db_connection,
+        state.chain_id(),
+        &user.db_user().name,
+        &address,
+    )
+    .await
+    {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
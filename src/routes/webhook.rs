@@ -0,0 +1,182 @@
+use axum::{
+    extract::{Path, Query, State},
+    routing::{delete, get},
+    Json, Router,
+};
+use reqwest::StatusCode;
+
+use crate::{
+    config::{AppState, WEBHOOK_DEFAULT_CONFIRMATIONS},
+    utils::{
+        db::webhook::{
+            delete_tracked_transaction, delete_webhook, fetch_user_tracked_transactions,
+            fetch_user_webhook_deliveries, fetch_user_webhooks, save_tracked_transaction,
+            save_webhook,
+        },
+        misc::parse_b256_from_str,
+        structs::{
+            auth::AuthUser,
+            webhook::{
+                RegisteredWebhook, TrackPayload, TrackedTransaction, TrackedTransactionsResponse,
+                WebhookDeliveriesQuery, WebhookDeliveriesResponse, WebhookPayload,
+                WebhooksResponse,
+            },
+        },
+        webhook::{check_webhook_url, generate_webhook_secret},
+    },
+};
+
+const MAX_WEBHOOK_DELIVERIES_LIMIT: i64 = 100;
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/", get(fetch_webhooks).post(register_webhook))
+        .route("/:id", delete(unregister_webhook))
+        .route("/deliveries", get(fetch_deliveries))
+        .route(
+            "/transactions",
+            get(fetch_tracked_transactions).post(track_transaction),
+        )
+        .route("/transactions/:hash", delete(untrack_transaction))
+}
+
+pub async fn fetch_webhooks(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<WebhooksResponse>, StatusCode> {
+    match fetch_user_webhooks(&state.db_connection, state.chain_id(), &user.db_user().name).await {
+        Ok(webhooks) => Ok(Json(WebhooksResponse { webhooks })),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+// the secret the deliveries are signed with is only returned here
+pub async fn register_webhook(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<WebhookPayload>,
+) -> Result<Json<RegisteredWebhook>, StatusCode> {
+    check_webhook_url(&payload.url)
+        .await
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let secret = generate_webhook_secret();
+    match save_webhook(
+        &state.db_connection,
+        state.chain_id(),
+        &user.db_user().name,
+        &payload.url,
+        &secret,
+    )
+    .await
+    {
+        Ok(webhook) => Ok(Json(RegisteredWebhook { webhook, secret })),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+pub async fn unregister_webhook(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, StatusCode> {
+    match delete_webhook(
+        &state.db_connection,
+        state.chain_id(),
+        &user.db_user().name,
+        id,
+    )
+    .await
+    {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+pub async fn fetch_deliveries(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(query): Query<WebhookDeliveriesQuery>,
+) -> Result<Json<WebhookDeliveriesResponse>, StatusCode> {
+    if !(1..=MAX_WEBHOOK_DELIVERIES_LIMIT).contains(&query.limit) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match fetch_user_webhook_deliveries(
+        &state.db_connection,
+        state.chain_id(),
+        &user.db_user().name,
+        query.limit,
+    )
+    .await
+    {
+        Ok(deliveries) => Ok(Json(WebhookDeliveriesResponse { deliveries })),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+pub async fn fetch_tracked_transactions(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<TrackedTransactionsResponse>, StatusCode> {
+    match fetch_user_tracked_transactions(
+        &state.db_connection,
+        state.chain_id(),
+        &user.db_user().name,
+    )
+    .await
+    {
+        Ok(transactions) => Ok(Json(TrackedTransactionsResponse { transactions })),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+pub async fn track_transaction(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(payload): Json<TrackPayload>,
+) -> Result<Json<TrackedTransaction>, StatusCode> {
+    let transaction_hash =
+        parse_b256_from_str(&payload.transaction_hash).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let confirmations = payload
+        .confirmations
+        .unwrap_or(*WEBHOOK_DEFAULT_CONFIRMATIONS);
+    if confirmations < 1 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match save_tracked_transaction(
+        &state.db_connection,
+        state.chain_id(),
+        &user.db_user().name,
+        &transaction_hash.to_string(),
+        confirmations,
+    )
+    .await
+    {
+        Ok(tracked) => Ok(Json(tracked)),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+pub async fn untrack_transaction(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Path(hash): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let transaction_hash = parse_b256_from_str(&hash).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    match delete_tracked_transaction(
+        &state.db_connection,
+        state.chain_id(),
+        &user.db_user().name,
+        &transaction_hash.to_string(),
+    )
+    .await
+    {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use alloy::{
+    dyn_abi::{DynSolValue, EventExt, JsonAbiExt},
+    json_abi::JsonAbi,
+    primitives::B256,
+};
+use serde_json::Value;
+
+use crate::config::AppState;
+
+use super::{
+    db::abi::fetch_contract_abis,
+    misc::parse_b256_from_str,
+    structs::{
+        abi::{DecodedCall, DecodedEvent, DecodedParam},
+        log::TransactionLog,
+        transaction::ResultTransaction,
+    },
+};
+
+// numbers are returned as decimal strings, as they might not fit in a JSON number
+pub fn dyn_sol_value_to_json(value: &DynSolValue) -> Value {
+    match value {
+        DynSolValue::Bool(value) => Value::Bool(*value),
+        DynSolValue::Int(value, _) => Value::String(value.to_string()),
+        DynSolValue::Uint(value, _) => Value::String(value.to_string()),
+        DynSolValue::FixedBytes(value, size) => {
+            Value::String(format!("0x{}", hex::encode(&value[..*size])))
+        }
+        DynSolValue::Address(value) => Value::String(value.to_string()),
+        DynSolValue::Function(value) => Value::String(value.to_string()),
+        DynSolValue::Bytes(value) => Value::String(format!("0x{}", hex::encode(value))),
+        DynSolValue::String(value) => Value::String(value.clone()),
+        DynSolValue::Array(values)
+        | DynSolValue::FixedArray(values)
+        | DynSolValue::Tuple(values) => {
+            Value::Array(values.iter().map(dyn_sol_value_to_json).collect())
+        }
+    }
+}
+
+pub fn decode_input(abi: &JsonAbi, input: &str) -> Option<DecodedCall> {
+    let data = hex::decode(input.trim_start_matches("0x")).ok()?;
+    if data.len() < 4 {
+        return None;
+    }
+
+    let function = abi
+        .functions()
+        .find(|function| function.selector().as_slice() == &data[..4])?;
+    let values = function.abi_decode_input(&data[4..], false).ok()?;
+
+    Some(DecodedCall {
+        function: function.name.clone(),
+        signature: function.signature(),
+        args: function
+            .inputs
+            .iter()
+            .zip(values.iter())
+            .map(|(param, value)| DecodedParam {
+                name: param.name.clone(),
+                kind: param.selector_type().to_string(),
+                value: dyn_sol_value_to_json(value),
+            })
+            .collect(),
+    })
+}
+
+pub fn decode_log(abi: &JsonAbi, log: &TransactionLog) -> Option<DecodedEvent> {
+    let topics: Vec<B256> = log
+        .topics
+        .iter()
+        .map(|topic| parse_b256_from_str(topic))
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let topic0 = topics.first()?;
+
+    // anonymous events can not be matched by their selector
+    let event = abi
+        .events()
+        .find(|event| !event.anonymous && event.selector() == *topic0)?;
+    let data = hex::decode(log.data.trim_start_matches("0x")).ok()?;
+    let decoded = event.decode_log_parts(topics, &data, false).ok()?;
+
+    // the decoded values are split in indexed and body values,
+    // merge them back in the order of the event inputs
+    let mut indexed = decoded.indexed.iter();
+    let mut body = decoded.body.iter();
+
+    Some(DecodedEvent {
+        event: event.name.clone(),
+        signature: event.signature(),
+        args: event
+            .inputs
+            .iter()
+            .map(|param| {
+                let value = if param.indexed {
+                    indexed.next()
+                } else {
+                    body.next()
+                };
+
+                DecodedParam {
+                    name: param.name.clone(),
+                    kind: param.selector_type().to_string(),
+                    value: value.map(dyn_sol_value_to_json).unwrap_or(Value::Null),
+                }
+            })
+            .collect(),
+    })
+}
+
+// fetches the registered ABIs of the addresses, keyed by lowercase address
+pub async fn fetch_abis(state: &AppState, addresses: Vec<String>) -> HashMap<String, JsonAbi> {
+    let mut addresses: Vec<String> = addresses
+        .iter()
+        .map(|address| address.to_lowercase())
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+
+    match fetch_contract_abis(&state.db_connection, state.chain_id(), addresses).await {
+        Ok(contract_abis) => contract_abis
+            .into_iter()
+            .filter_map(|contract_abi| {
+                serde_json::from_value::<JsonAbi>(contract_abi.abi.0)
+                    .ok()
+                    .map(|abi| (contract_abi.address, abi))
+            })
+            .collect(),
+        Err(error) => {
+            println!("Failed to fetch abis from db: {}", error);
+            HashMap::new()
+        }
+    }
+}
+
+fn decode_logs_with(abis: &HashMap<String, JsonAbi>, logs: &mut [TransactionLog]) {
+    for log in logs.iter_mut() {
+        if let Some(abi) = abis.get(&log.address.to_lowercase()) {
+            log.decoded = decode_log(abi, log);
+        }
+    }
+}
+
+// decodes the input and the attached logs of the trxs with the registered ABIs
+pub async fn decode_transactions(state: &AppState, transactions: &mut [ResultTransaction]) {
+    let mut addresses: Vec<String> = vec![];
+    for trx in transactions.iter() {
+        addresses.extend(trx.to.clone());
+        if let Some(logs) = &trx.logs {
+            addresses.extend(logs.iter().map(|log| log.address.clone()));
+        }
+    }
+
+    if addresses.is_empty() {
+        return;
+    }
+
+    let abis = fetch_abis(state, addresses).await;
+    if abis.is_empty() {
+        return;
+    }
+
+    for trx in transactions.iter_mut() {
+        if let Some(abi) = trx.to.as_ref().and_then(|to| abis.get(&to.to_lowercase())) {
+            trx.decoded_input = decode_input(abi, &trx.input);
+        }
+        if let Some(logs) = trx.logs.as_mut() {
+            decode_logs_with(&abis, logs);
+        }
+    }
+}
+
+// decodes the logs with the registered ABIs of their emitting contracts
+pub async fn decode_logs(state: &AppState, logs: &mut [TransactionLog]) {
+    if logs.is_empty() {
+        return;
+    }
+
+    let abis = fetch_abis(state, logs.iter().map(|log| log.address.clone()).collect()).await;
+    decode_logs_with(&abis, logs);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static ERC20_ABI: &str = r#"[
+        {
+            "type": "function",
+            "name": "transfer",
+            "inputs": [
+                { "name": "to", "type": "address" },
+                { "name": "amount", "type": "uint256" }
+            ],
+            "outputs": [{ "name": "", "type": "bool" }],
+            "stateMutability": "nonpayable"
+        },
+        {
+            "type": "event",
+            "name": "Transfer",
+            "inputs": [
+                { "name": "from", "type": "address", "indexed": true },
+                { "name": "to", "type": "address", "indexed": true },
+                { "name": "value", "type": "uint256", "indexed": false }
+            ],
+            "anonymous": false
+        }
+    ]"#;
+
+    fn fixture_abi() -> JsonAbi {
+        serde_json::from_str(ERC20_ABI).expect("Invalid test abi")
+    }
+
+    #[test]
+    fn test_decode_input() {
+        let input = "0xa9059cbb\
+            0000000000000000000000005520a8a1723fdc8a8e64da1f348cc1991c13c1c3\
+            00000000000000000000000000000000000000000000000000000000000003e8";
+
+        let decoded = decode_input(&fixture_abi(), input).expect("Failed to decode input");
+
+        assert_eq!(decoded.function, "transfer");
+        assert_eq!(decoded.signature, "transfer(address,uint256)");
+        assert_eq!(decoded.args[0].name, "to");
+        assert_eq!(decoded.args[0].kind, "address");
+        assert_eq!(
+            decoded.args[0].value,
+            Value::String("0x5520a8a1723Fdc8a8e64Da1f348CC1991C13C1C3".to_string())
+        );
+        assert_eq!(decoded.args[1].value, Value::String("1000".to_string()));
+    }
+
+    #[test]
+    fn test_decode_input_unknown_selector() {
+        assert!(decode_input(&fixture_abi(), "0xdeadbeef").is_none());
+        assert!(decode_input(&fixture_abi(), "0x").is_none());
+    }
+
+    #[test]
+    fn test_decode_log() {
+        let log = TransactionLog {
+            transaction_hash: "hash1".to_string(),
+            log_index: 0,
+            address: "0xea58fcA6849d79EAd1f26608855c2D6407d54Ce2".to_string(),
+            topics: vec![
+                "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef".to_string(),
+                "0x0000000000000000000000005520a8a1723fdc8a8e64da1f348cc1991c13c1c3".to_string(),
+                "0x000000000000000000000000ea58fca6849d79ead1f26608855c2d6407d54ce2".to_string(),
+            ],
+            data: "0x00000000000000000000000000000000000000000000000000000000000003e8".to_string(),
+            removed: false,
+            decoded: None,
+            signature: None,
+        };
+
+        let decoded = decode_log(&fixture_abi(), &log).expect("Failed to decode log");
+
+        assert_eq!(decoded.event, "Transfer");
+        assert_eq!(decoded.signature, "Transfer(address,address,uint256)");
+        assert_eq!(
+            decoded
+                .args
+                .iter()
+                .map(|arg| arg.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["from", "to", "value"]
+        );
+        assert_eq!(decoded.args[2].value, Value::String("1000".to_string()));
+    }
+}
@@ -0,0 +1,81 @@
+use std::str::FromStr;
+
+use alloy::primitives::Address;
+
+use super::structs::transaction::ResultTransaction;
+
+// parses the address case-insensitively, a mixed-case address must have a valid
+// EIP-55 checksum, returns the lowercase address used for matching
+pub fn normalize_address(address: &str) -> Result<String, String> {
+    let hex = address.trim_start_matches("0x");
+    let is_mixed_case =
+        hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+
+    let parsed = if is_mixed_case {
+        Address::parse_checksummed(address, None).map_err(|e| e.to_string())?
+    } else {
+        Address::from_str(address).map_err(|e| e.to_string())?
+    };
+
+    Ok(parsed.to_string().to_lowercase())
+}
+
+// the cursor is the position of the last returned trx, in the descending order
+// of block number and trx hash
+pub fn encode_cursor(trx: &ResultTransaction) -> String {
+    format!(
+        "{}_{}",
+        trx.block_number.unwrap_or_default(),
+        trx.transaction_hash
+    )
+}
+
+pub fn decode_cursor(cursor: &str) -> Result<(i32, String), String> {
+    let (block_number, transaction_hash) = cursor
+        .split_once('_')
+        .ok_or(format!("Invalid cursor {}", cursor))?;
+    let block_number = block_number
+        .parse::<i32>()
+        .map_err(|_| format!("Invalid cursor {}", cursor))?;
+
+    Ok((block_number, transaction_hash.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_address() {
+        let lowercase = "0x5520a8a1723fdc8a8e64da1f348cc1991c13c1c3";
+
+        assert_eq!(normalize_address(lowercase), Ok(lowercase.to_string()));
+        assert_eq!(
+            normalize_address("0x5520A8A1723FDC8A8E64DA1F348CC1991C13C1C3"),
+            Ok(lowercase.to_string())
+        );
+        assert_eq!(
+            normalize_address("0x5520a8a1723Fdc8a8e64Da1f348CC1991C13C1C3"),
+            Ok(lowercase.to_string())
+        );
+        // mixed case with an invalid checksum
+        assert!(normalize_address("0x5520A8a1723Fdc8a8e64Da1f348CC1991C13C1C3").is_err());
+        assert!(normalize_address("0x5520").is_err());
+    }
+
+    #[test]
+    fn test_cursor_roundtrip() {
+        let trx = ResultTransaction {
+            transaction_hash: "0xabc".to_string(),
+            block_number: Some(100),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            decode_cursor(&encode_cursor(&trx)),
+            Ok((100, "0xabc".to_string()))
+        );
+        assert!(decode_cursor("100").is_err());
+        assert!(decode_cursor("block_0xabc").is_err());
+    }
+}
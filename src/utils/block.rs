@@ -0,0 +1,239 @@
+use std::{collections::HashMap, str::FromStr};
+
+use alloy::{
+    eips::{BlockId, BlockNumberOrTag},
+    providers::Provider,
+    rpc::{
+        client::BatchRequest,
+        types::{Block, BlockTransactionsKind},
+    },
+};
+
+use crate::config::AppState;
+
+use super::{
+    chain::{fetch_finality_heads, is_transport_failure, FinalityHeads},
+    db::block::{check_block_in_db, fetch_block_timestamps, save_block_to_db},
+    misc::parse_b256_from_str,
+    reorg::is_non_canonical,
+    structs::{block::ResultBlock, chain::ChainError},
+};
+
+// parses a block hash, a decimal or hex block number or a block tag e.g. latest
+pub fn parse_block_id(number_or_hash: &str) -> Result<BlockId, String> {
+    if number_or_hash.len() == 66 {
+        return parse_b256_from_str(number_or_hash).map(BlockId::from);
+    }
+
+    if let Ok(block_number) = number_or_hash.parse::<u64>() {
+        return Ok(BlockId::number(block_number));
+    }
+
+    match BlockNumberOrTag::from_str(number_or_hash) {
+        Ok(BlockNumberOrTag::Pending) => Err("Pending blocks are not supported".to_string()),
+        Ok(block_number_or_tag) => Ok(BlockId::Number(block_number_or_tag)),
+        Err(_) => Err(format!("Invalid block number or hash {}", number_or_hash)),
+    }
+}
+
+pub fn map_block(block: &Block, heads: &FinalityHeads) -> ResultBlock {
+    ResultBlock {
+        block_hash: block.header.hash.to_string(),
+        block_number: block.header.number as i32,
+        parent_hash: block.header.parent_hash.to_string(),
+        timestamp: block.header.timestamp as i64,
+        base_fee_per_gas: block
+            .header
+            .base_fee_per_gas
+            .map(|base_fee| base_fee.to_string()),
+        gas_used: block.header.gas_used as i64,
+        gas_limit: block.header.gas_limit as i64,
+        miner: block.header.beneficiary.to_string(),
+        transaction_hashes: block
+            .transactions
+            .hashes()
+            .map(|hash| hash.to_string())
+            .collect(),
+        state: heads.state_of(block.header.number),
+    }
+}
+
+// saves the block header, unless another block was recorded as canonical for its height
+pub async fn save_fetched_block(state: &AppState, block: &ResultBlock) {
+    match is_non_canonical(state, block.block_number, &block.block_hash).await {
+        Ok(false) => {}
+        Ok(true) => {
+            println!(
+                "Block {} is not canonical for its height, not cached",
+                block.block_hash
+            );
+            return;
+        }
+        Err(error) => {
+            println!(
+                "Failed to check block - {} in db, error:{}",
+                block.block_hash, error
+            );
+            return;
+        }
+    }
+
+    if let Err(error) = save_block_to_db(&state.db_connection, state.chain_id(), block).await {
+        println!(
+            "Failed to save block - {} in db, error:{}",
+            block.block_hash, error
+        );
+    }
+}
+
+// returns the cached block, fetching it from chain when missing,
+// blocks requested by tag are always fetched as the tag moves
+pub async fn fetch_block(state: &AppState, block_id: BlockId) -> Result<ResultBlock, ChainError> {
+    let cached_block = match block_id {
+        BlockId::Hash(block_hash) => {
+            check_block_in_db(
+                &state.db_connection,
+                state.chain_id(),
+                Some(&block_hash.block_hash.to_string()),
+                None,
+            )
+            .await
+        }
+        BlockId::Number(BlockNumberOrTag::Number(block_number)) => {
+            check_block_in_db(
+                &state.db_connection,
+                state.chain_id(),
+                None,
+                Some(block_number as i32),
+            )
+            .await
+        }
+        BlockId::Number(_) => Ok(None),
+    };
+
+    match cached_block {
+        Ok(Some(block)) => {
+            println!("Block {} fetched from db", block.block_number);
+            return Ok(block);
+        }
+        Ok(None) => {}
+        Err(error) => println!("Failed to fetch block from db: {}", error),
+    }
+
+    // the header fetch goes through the same breaker as the trx fetches
+    let permit = state
+        .circuit_breaker
+        .try_acquire()
+        .ok_or(ChainError::CircuitOpen)?;
+
+    let (block, heads) = tokio::join!(
+        state
+            .eth_client
+            .get_block(block_id, BlockTransactionsKind::Hashes),
+        fetch_finality_heads(state)
+    );
+    permit.record(!is_transport_failure(&block));
+
+    match block {
+        Ok(Some(block)) => {
+            let block = map_block(&block, &heads);
+            save_fetched_block(state, &block).await;
+            println!("Block {} fetched and saved in db", block.block_number);
+
+            Ok(block)
+        }
+        Ok(None) => Err(ChainError::BlockNotFound(block_id.to_string())),
+        Err(error) => Err(error.into()),
+    }
+}
+
+// resolves the timestamps of the block hashes from the cached block headers,
+// the missing headers are fetched in a single JSON-RPC batch and cached
+pub async fn resolve_block_timestamps(
+    state: &AppState,
+    mut block_hashes: Vec<String>,
+    heads: &FinalityHeads,
+) -> HashMap<String, i64> {
+    block_hashes.sort();
+    block_hashes.dedup();
+
+    let mut timestamps: HashMap<String, i64> =
+        match fetch_block_timestamps(&state.db_connection, state.chain_id(), block_hashes.clone())
+            .await
+        {
+            Ok(timestamps) => timestamps.into_iter().collect(),
+            Err(error) => {
+                println!("Failed to fetch block timestamps from db: {}", error);
+                HashMap::new()
+            }
+        };
+
+    let mut batch = BatchRequest::new(state.eth_client.client());
+    let mut waiters = vec![];
+    for block_hash in block_hashes.iter() {
+        if timestamps.contains_key(block_hash) {
+            continue;
+        }
+
+        if let Ok(b256_block_hash) = parse_b256_from_str(block_hash) {
+            if let Ok(waiter) =
+                batch.add_call::<_, Option<Block>>("eth_getBlockByHash", &(b256_block_hash, false))
+            {
+                waiters.push(waiter);
+            }
+        }
+    }
+
+    if waiters.is_empty() {
+        return timestamps;
+    }
+
+    // the headers are served from the cache only while the breaker is open
+    let Some(permit) = state.circuit_breaker.try_acquire() else {
+        return timestamps;
+    };
+
+    let sent = batch.send().await;
+    permit.record(sent.is_ok());
+    if let Err(error) = sent {
+        println!("Failed to fetch blocks, error:{}", error);
+        return timestamps;
+    }
+
+    for waiter in waiters {
+        match waiter.await {
+            Ok(Some(block)) => {
+                let block = map_block(&block, heads);
+                save_fetched_block(state, &block).await;
+                timestamps.insert(block.block_hash, block.timestamp);
+            }
+            Ok(None) => {}
+            Err(error) => println!("Failed to fetch block, error:{}", error),
+        }
+    }
+
+    timestamps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_block_id() {
+        assert_eq!(parse_block_id("100"), Ok(BlockId::number(100)));
+        assert_eq!(parse_block_id("0x64"), Ok(BlockId::number(100)));
+        assert_eq!(parse_block_id("latest"), Ok(BlockId::latest()));
+        assert_eq!(
+            parse_block_id("0xbdb191d7ee7c25cc144b4ba35ea06cc912762495a66ad0336a7cabc5ab31c36f"),
+            Ok(BlockId::from(
+                parse_b256_from_str(
+                    "0xbdb191d7ee7c25cc144b4ba35ea06cc912762495a66ad0336a7cabc5ab31c36f"
+                )
+                .unwrap()
+            ))
+        );
+        assert!(parse_block_id("pending").is_err());
+        assert!(parse_block_id("block").is_err());
+    }
+}
@@ -1,127 +1,478 @@
 use crate::config::AppState;
 use alloy::{
-    consensus::Transaction,
-    primitives::FixedBytes,
+    consensus::{Transaction, Typed2718},
+    eips::BlockNumberOrTag,
     providers::Provider,
-    rpc::types::{Filter, Log, TransactionReceipt},
+    rpc::{
+        client::BatchRequest,
+        types::{Block, BlockTransactionsKind, Transaction as RpcTransaction, TransactionReceipt},
+    },
     transports::{RpcError, TransportErrorKind},
 };
+use sqlx::types::Json;
 
 use super::{
-    db::transaction::save_transaction_to_db, misc::parse_b256_from_str,
-    structs::transaction::ResultTransaction,
+    block::resolve_block_timestamps,
+    db::{
+        log::{delete_transaction_logs, save_log_to_db},
+        transaction::{save_transaction_block_timestamp, save_transaction_to_db},
+        transfer::{delete_transaction_transfers, save_token_transfer},
+    },
+    misc::{parse_b256_from_str, unix_to_iso8601},
+    reorg::is_non_canonical,
+    structs::{
+        chain::ChainError,
+        log::TransactionLog,
+        transaction::{ResultTransaction, TransactionState},
+    },
+    transfer::extract_token_transfers,
 };
 
-fn unwrap_receipt(
-    receipt: Result<Option<TransactionReceipt>, RpcError<TransportErrorKind>>,
-    b256_transaction_hash: FixedBytes<32>,
-) -> Result<TransactionReceipt, String> {
-    match receipt.unwrap() {
-        Some(receipt_result) => Ok(receipt_result),
-        None => Err(format!(
-            "No receipt found for trx_hash {}",
-            b256_transaction_hash
-        )),
+// the heights of the node's safe and finalized blocks,
+// None when the node does not support the block tag
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FinalityHeads {
+    pub safe: Option<u64>,
+    pub finalized: Option<u64>,
+}
+impl FinalityHeads {
+    fn from_blocks(
+        safe: Result<Option<Block>, RpcError<TransportErrorKind>>,
+        finalized: Result<Option<Block>, RpcError<TransportErrorKind>>,
+    ) -> FinalityHeads {
+        FinalityHeads {
+            safe: safe.ok().flatten().map(|block| block.header.number),
+            finalized: finalized.ok().flatten().map(|block| block.header.number),
+        }
+    }
+
+    pub fn state_of(&self, block_number: u64) -> TransactionState {
+        if self
+            .finalized
+            .is_some_and(|finalized| block_number <= finalized)
+        {
+            TransactionState::Finalized
+        } else if self.safe.is_some_and(|safe| block_number <= safe) {
+            TransactionState::Safe
+        } else {
+            TransactionState::Latest
+        }
+    }
+}
+
+pub async fn fetch_finality_heads(state: &AppState) -> FinalityHeads {
+    let (safe, finalized) = tokio::join!(
+        state
+            .eth_client
+            .get_block_by_number(BlockNumberOrTag::Safe, BlockTransactionsKind::Hashes),
+        state
+            .eth_client
+            .get_block_by_number(BlockNumberOrTag::Finalized, BlockTransactionsKind::Hashes)
+    );
+
+    FinalityHeads::from_blocks(safe, finalized)
+}
+
+fn map_transaction(
+    transaction_hash: &str,
+    trx_result: RpcTransaction,
+    receipt_option: Option<TransactionReceipt>,
+    heads: &FinalityHeads,
+) -> (ResultTransaction, Vec<TransactionLog>) {
+    // the receipt already contains the logs emitted by the trx
+    let logs: Vec<TransactionLog> = match &receipt_option {
+        Some(receipt_value) => receipt_value
+            .inner
+            .logs()
+            .iter()
+            .map(|log| TransactionLog::from_rpc_log(transaction_hash, log))
+            .collect(),
+        _ => vec![],
+    };
+
+    // a trx without a block or a receipt is still waiting to be mined
+    let state = match (trx_result.block_number, &receipt_option) {
+        (Some(block_number), Some(_)) => heads.state_of(block_number),
+        _ => TransactionState::Pending,
+    };
+
+    let (gas_used, effective_gas_price) = match &receipt_option {
+        Some(receipt_value) => (
+            Some(receipt_value.gas_used as i64),
+            Some(receipt_value.effective_gas_price.to_string()),
+        ),
+        _ => (None, None),
+    };
+
+    let mapped_trx = ResultTransaction {
+        block_hash: trx_result
+            .block_hash
+            .map(|block_hash| block_hash.to_string()),
+        block_number: trx_result
+            .block_number
+            .map(|block_number| block_number as i32),
+        // resolved from the block header after mapping
+        block_timestamp: None,
+        block_time: None,
+        contract_address: match receipt_option.clone() {
+            Some(receipt_value) => receipt_value
+                .contract_address
+                .map(|contract_add| contract_add.to_string()),
+            _ => None,
+        },
+        from: trx_result.from.to_string(),
+        to: trx_result.to().map(|to_value| to_value.to_string()),
+        input: trx_result.input().to_string(),
+        logs_count: logs.len() as i32,
+        transaction_hash: transaction_hash.to_owned(),
+        transaction_status: receipt_option.map(|receipt_value| receipt_value.status() as i16),
+        value: trx_result.value().to_string(),
+        transaction_type: Some(trx_result.ty() as i16),
+        nonce: Some(trx_result.nonce() as i64),
+        gas_limit: Some(trx_result.gas_limit() as i64),
+        gas_used,
+        effective_gas_price,
+        // legacy and EIP-2930 trxs only have a gas price
+        max_fee_per_gas: trx_result
+            .is_dynamic_fee()
+            .then(|| trx_result.max_fee_per_gas().to_string()),
+        max_priority_fee_per_gas: trx_result
+            .max_priority_fee_per_gas()
+            .map(|fee| fee.to_string()),
+        access_list: trx_result
+            .access_list()
+            .and_then(|access_list| serde_json::to_value(access_list).ok())
+            .map(Json),
+        blob_versioned_hashes: trx_result
+            .blob_versioned_hashes()
+            .map(|hashes| hashes.iter().map(|hash| hash.to_string()).collect()),
+        max_fee_per_blob_gas: trx_result.max_fee_per_blob_gas().map(|fee| fee.to_string()),
+        authorization_list: trx_result
+            .authorization_list()
+            .and_then(|authorization_list| serde_json::to_value(authorization_list).ok())
+            .map(Json),
+        // resolved on request, as it needs an extra eth_call
+        revert_reason: None,
+        revert_data: None,
+        state,
+        logs: None,
+        decoded_input: None,
+        input_signature: None,
+        token_transfers: None,
+    };
+
+    (mapped_trx, logs)
+}
+
+// sets the block time of the mapped trxs, reusing the cached block headers
+async fn set_block_timestamps(
+    state: &AppState,
+    transactions: &mut [&mut ResultTransaction],
+    heads: &FinalityHeads,
+) {
+    let block_hashes: Vec<String> = transactions
+        .iter()
+        .filter_map(|trx| trx.block_hash.clone())
+        .collect();
+    if block_hashes.is_empty() {
+        return;
+    }
+
+    let timestamps = resolve_block_timestamps(state, block_hashes, heads).await;
+    for trx in transactions.iter_mut() {
+        trx.block_timestamp = trx
+            .block_hash
+            .as_ref()
+            .and_then(|block_hash| timestamps.get(block_hash).copied());
+        trx.block_time = trx.block_timestamp.and_then(unix_to_iso8601);
     }
 }
 
-async fn fetch_logs(
+// a failed write is returned instead of serving a trx the cache does not hold
+// fills in the block time of cached trxs whose block header could not be fetched when they
+// were saved, the resolved timestamps are saved with the trxs
+pub async fn fill_missing_block_timestamps(
     state: &AppState,
-    block_hash: FixedBytes<32>,
-    trx_hash: FixedBytes<32>,
-) -> Result<Vec<Log>, String> {
-    // fetch log
-    let logs_filter = Filter::new().at_block_hash(block_hash);
-
-    match state.eth_client.get_logs(&logs_filter).await {
-        Ok(block_logs) => {
-            let transaction_logs: Vec<Log> = block_logs
-                .into_iter()
-                .filter(|log| log.transaction_hash == Some(trx_hash))
-                .collect();
-
-            Ok(transaction_logs)
+    transactions: &mut [ResultTransaction],
+) {
+    let mut missing: Vec<&mut ResultTransaction> = transactions
+        .iter_mut()
+        .filter(|trx| trx.block_timestamp.is_none() && trx.block_hash.is_some())
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+
+    // the finality of the headers is not known here, fetched ones are cached as latest
+    // and revalidated after the ttl
+    set_block_timestamps(state, &mut missing, &FinalityHeads::default()).await;
+
+    for trx in missing {
+        if let Some(block_timestamp) = trx.block_timestamp {
+            if let Err(error) = save_transaction_block_timestamp(
+                &state.db_connection,
+                state.chain_id(),
+                &trx.transaction_hash,
+                block_timestamp,
+            )
+            .await
+            {
+                println!(
+                    "Failed to save block timestamp of trx - {} in db, error:{}",
+                    trx.transaction_hash, error
+                );
+            }
         }
-        Err(msg) => Err(format!("Failed to fetch logs: {}", msg)),
     }
 }
 
-pub async fn fetch_from_chain(
-    transaction_hash: &String,
+async fn save_fetched_transaction(
     state: &AppState,
-    result: &mut Vec<ResultTransaction>,
-) -> Result<(), String> {
-    match parse_b256_from_str(transaction_hash) {
-        Ok(b256_transaction_hash) => {
-            let (transaction, receipt) = tokio::join!(
-                state
-                    .eth_client
-                    .get_transaction_by_hash(b256_transaction_hash),
-                state
-                    .eth_client
-                    .get_transaction_receipt(b256_transaction_hash)
+    trx: &ResultTransaction,
+    logs: &[TransactionLog],
+) -> Result<(), ChainError> {
+    // pending trxs are not cached, so they get fetched again until they are mined
+    if trx.state == TransactionState::Pending {
+        return Ok(());
+    }
+
+    let db_error = |action: &str, error: sqlx::Error| {
+        ChainError::Database(format!(
+            "Failed to {} of trx - {}: {}",
+            action, trx.transaction_hash, error
+        ))
+    };
+
+    // a trx of a block that is not canonical for its height comes from a lagging endpoint
+    // or an orphaned block, it is not cached
+    if let (Some(block_hash), Some(block_number)) = (&trx.block_hash, trx.block_number) {
+        if is_non_canonical(state, block_number, block_hash)
+            .await
+            .map_err(|e| db_error("check the block", e))?
+        {
+            println!(
+                "{} is in the non canonical block {}, not cached",
+                trx.transaction_hash, block_hash
             );
+            return Ok(());
+        }
+    }
 
-            match transaction.unwrap() {
-                Some(trx_result) => {
-                    let block_hash = trx_result.block_hash.unwrap_or(FixedBytes::default());
-
-                    let receipt_option: Option<TransactionReceipt> =
-                        match unwrap_receipt(receipt, b256_transaction_hash) {
-                            Ok(rec) => Option::from(rec),
-                            _ => None,
-                        };
-
-                    let mapped_trx = ResultTransaction {
-                        block_hash: block_hash.to_string(),
-                        block_number: trx_result.block_number.unwrap_or(0) as i32,
-                        contract_address: match receipt_option.clone() {
-                            Some(receipt_value) => match receipt_value.contract_address {
-                                Some(contract_add) => Option::from(contract_add.to_string()),
-                                None => None,
-                            },
-                            _ => None,
-                        },
-                        from: trx_result.from.to_string(),
-                        to: match trx_result.to() {
-                            Some(to_value) => Option::from(to_value.to_string()),
-                            None => None,
-                        },
-                        input: trx_result.input().to_string(),
-                        logs_count: match fetch_logs(&state, block_hash, b256_transaction_hash)
-                            .await
-                        {
-                            Ok(logs) => logs.len() as i32,
-                            Err(_) => 0,
-                        },
-                        transaction_hash: transaction_hash.to_owned(),
-                        transaction_status: match receipt_option.clone() {
-                            Some(receipt_value) => receipt_value.status() as i16,
-                            _ => 0,
-                        },
-                        value: trx_result.value().to_string(),
-                    };
-
-                    // save trx to db
-                    save_transaction_to_db(&state.db_connection, &mapped_trx)
-                        .await
-                        .expect(
-                            format!("Failed to save trx - {} in db", transaction_hash).as_str(),
-                        );
-
-                    // add to result VEC
-                    result.push(mapped_trx);
-                }
-                None => {
-                    return Err(format!(
-                        "No transaction found for trx_hash {}",
-                        b256_transaction_hash
-                    ));
-                }
+    // save trx to db
+    save_transaction_to_db(&state.db_connection, state.chain_id(), trx)
+        .await
+        .map_err(|e| db_error("save", e))?;
+
+    // save trx logs to db, replacing the logs of a previous fetch
+    delete_transaction_logs(
+        &state.db_connection,
+        state.chain_id(),
+        &trx.transaction_hash,
+    )
+    .await
+    .map_err(|e| db_error("delete logs", e))?;
+    for log in logs.iter() {
+        save_log_to_db(&state.db_connection, state.chain_id(), log)
+            .await
+            .map_err(|e| db_error("save logs", e))?;
+    }
+
+    // save the token transfers found in the logs, replacing the ones of a previous fetch
+    delete_transaction_transfers(
+        &state.db_connection,
+        state.chain_id(),
+        &trx.transaction_hash,
+    )
+    .await
+    .map_err(|e| db_error("delete token transfers", e))?;
+    for transfer in extract_token_transfers(logs).iter() {
+        save_token_transfer(&state.db_connection, state.chain_id(), transfer)
+            .await
+            .map_err(|e| db_error("save token transfers", e))?;
+    }
+
+    Ok(())
+}
+
+// JSON-RPC error responses are answers of the node, only failed calls count against the breaker
+pub fn is_transport_failure<T>(result: &Result<T, RpcError<TransportErrorKind>>) -> bool {
+    matches!(result, Err(RpcError::Transport(_)))
+}
+
+pub async fn fetch_from_chain(
+    transaction_hash: &str,
+    state: &AppState,
+) -> Result<ResultTransaction, ChainError> {
+    let b256_transaction_hash =
+        parse_b256_from_str(transaction_hash).map_err(ChainError::InvalidHash)?;
+
+    let permit = state
+        .circuit_breaker
+        .try_acquire()
+        .ok_or(ChainError::CircuitOpen)?;
+
+    let (transaction, receipt, heads) = tokio::join!(
+        state
+            .eth_client
+            .get_transaction_by_hash(b256_transaction_hash),
+        state
+            .eth_client
+            .get_transaction_receipt(b256_transaction_hash),
+        fetch_finality_heads(state)
+    );
+
+    permit.record(!is_transport_failure(&transaction) && !is_transport_failure(&receipt));
+
+    let trx_result =
+        transaction?.ok_or_else(|| ChainError::NotFound(b256_transaction_hash.to_string()))?;
+    // a missing receipt means the trx is pending, a failed receipt call must not be taken for it
+    let receipt_option = receipt?;
+
+    let (mut mapped_trx, logs) =
+        map_transaction(transaction_hash, trx_result, receipt_option, &heads);
+    set_block_timestamps(state, &mut [&mut mapped_trx], &heads).await;
+    save_fetched_transaction(state, &mapped_trx, &logs).await?;
+
+    Ok(mapped_trx)
+}
+
+// fetches all transaction hashes with a single JSON-RPC batch call,
+// the results are in the same order as the passed hashes
+pub async fn fetch_batch_from_chain(
+    transaction_hashes: &[String],
+    state: &AppState,
+) -> Vec<Result<ResultTransaction, ChainError>> {
+    let mut result: Vec<Result<ResultTransaction, ChainError>> = transaction_hashes
+        .iter()
+        .map(|transaction_hash| Err(ChainError::NotFound(transaction_hash.clone())))
+        .collect();
+    let mut batch = BatchRequest::new(state.eth_client.client());
+    let mut waiters = vec![];
+
+    for (i, transaction_hash) in transaction_hashes.iter().enumerate() {
+        let b256_transaction_hash = match parse_b256_from_str(transaction_hash) {
+            Ok(b256_transaction_hash) => b256_transaction_hash,
+            Err(err_msg) => {
+                result[i] = Err(ChainError::InvalidHash(err_msg));
+                continue;
             }
+        };
+
+        let transaction = batch.add_call::<_, Option<RpcTransaction>>(
+            "eth_getTransactionByHash",
+            &(b256_transaction_hash,),
+        );
+        let receipt = batch.add_call::<_, Option<TransactionReceipt>>(
+            "eth_getTransactionReceipt",
+            &(b256_transaction_hash,),
+        );
+
+        match (transaction, receipt) {
+            (Ok(transaction), Ok(receipt)) => {
+                waiters.push((i, b256_transaction_hash, transaction, receipt))
+            }
+            (Err(error), _) | (_, Err(error)) => result[i] = Err(error.into()),
         }
-        Err(err_msg) => return Err(err_msg),
+    }
+
+    if waiters.is_empty() {
+        return result;
+    }
+
+    // the finality heads are fetched within the same batch
+    let safe = batch
+        .add_call::<_, Option<Block>>("eth_getBlockByNumber", &(BlockNumberOrTag::Safe, false));
+    let finalized = batch.add_call::<_, Option<Block>>(
+        "eth_getBlockByNumber",
+        &(BlockNumberOrTag::Finalized, false),
+    );
+
+    let Some(permit) = state.circuit_breaker.try_acquire() else {
+        for (i, _, _, _) in waiters {
+            result[i] = Err(ChainError::CircuitOpen);
+        }
+
+        return result;
     };
 
-    Ok(())
+    let sent = batch.send().await;
+    permit.record(sent.is_ok());
+    if let Err(error) = sent {
+        let error = ChainError::from(error);
+        for (i, _, _, _) in waiters {
+            result[i] = Err(error.clone());
+        }
+
+        return result;
+    }
+
+    let heads = match (safe, finalized) {
+        (Ok(safe), Ok(finalized)) => {
+            let (safe, finalized) = tokio::join!(safe, finalized);
+            FinalityHeads::from_blocks(safe, finalized)
+        }
+        _ => FinalityHeads::default(),
+    };
+
+    let mut mapped: Vec<(usize, ResultTransaction, Vec<TransactionLog>)> = vec![];
+    for (i, b256_transaction_hash, transaction, receipt) in waiters {
+        let (transaction, receipt) = tokio::join!(transaction, receipt);
+
+        match (transaction, receipt) {
+            (Ok(Some(trx_result)), Ok(receipt_option)) => {
+                let (mapped_trx, logs) =
+                    map_transaction(&transaction_hashes[i], trx_result, receipt_option, &heads);
+                mapped.push((i, mapped_trx, logs));
+            }
+            (Ok(None), _) => {
+                result[i] = Err(ChainError::NotFound(b256_transaction_hash.to_string()))
+            }
+            (Err(error), _) | (_, Err(error)) => result[i] = Err(error.into()),
+        };
+    }
+
+    // the block headers of all trxs are resolved at once
+    set_block_timestamps(
+        state,
+        &mut mapped
+            .iter_mut()
+            .map(|(_, mapped_trx, _)| mapped_trx)
+            .collect::<Vec<_>>(),
+        &heads,
+    )
+    .await;
+
+    for (i, mapped_trx, logs) in mapped {
+        result[i] = save_fetched_transaction(state, &mapped_trx, &logs)
+            .await
+            .map(|_| mapped_trx);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_finality_heads_state_of() {
+        let heads = FinalityHeads {
+            safe: Some(200),
+            finalized: Some(100),
+        };
+
+        assert_eq!(heads.state_of(50), TransactionState::Finalized);
+        assert_eq!(heads.state_of(100), TransactionState::Finalized);
+        assert_eq!(heads.state_of(150), TransactionState::Safe);
+        assert_eq!(heads.state_of(200), TransactionState::Safe);
+        assert_eq!(heads.state_of(201), TransactionState::Latest);
+    }
+
+    #[test]
+    fn test_finality_heads_state_of_unsupported_tags() {
+        let heads = FinalityHeads::default();
+
+        assert_eq!(heads.state_of(0), TransactionState::Latest);
+    }
 }
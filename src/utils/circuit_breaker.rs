@@ -0,0 +1,244 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::State,
+    http::{HeaderName, HeaderValue},
+    response::Response,
+};
+
+use crate::config::{AppState, CIRCUIT_BREAKER_FAILURE_THRESHOLD, CIRCUIT_BREAKER_OPEN_SECS};
+
+use super::structs::circuit_breaker::{CircuitBreakerStatus, CircuitState};
+
+#[derive(Debug)]
+struct CircuitBreakerInner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probing: bool, // a probe call is in flight while half-open
+    times_opened: u64,
+    refused_calls: u64,
+}
+impl Default for CircuitBreakerInner {
+    fn default() -> Self {
+        CircuitBreakerInner {
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            opened_at: None,
+            probing: false,
+            times_opened: 0,
+            refused_calls: 0,
+        }
+    }
+}
+impl CircuitBreakerInner {
+    // an open breaker turns half-open once CIRCUIT_BREAKER_OPEN_SECS have passed
+    fn refresh(&mut self, now: Instant) {
+        let open_for = Duration::from_secs(*CIRCUIT_BREAKER_OPEN_SECS);
+
+        if self.state == CircuitState::Open
+            && self
+                .opened_at
+                .is_some_and(|opened_at| now.duration_since(opened_at) >= open_for)
+        {
+            self.state = CircuitState::HalfOpen;
+            self.probing = false;
+        }
+    }
+
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        self.refresh(now);
+
+        let allowed = match self.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            // only a single probe is let through, the other calls wait for its outcome
+            CircuitState::HalfOpen => !std::mem::replace(&mut self.probing, true),
+        };
+        if !allowed {
+            self.refused_calls += 1;
+        }
+
+        allowed
+    }
+
+    // a probe cancelled before its outcome was recorded lets the next call probe instead
+    fn release(&mut self) {
+        if self.state == CircuitState::HalfOpen {
+            self.probing = false;
+        }
+    }
+
+    fn record(&mut self, success: bool, now: Instant) {
+        if success {
+            self.state = CircuitState::Closed;
+            self.consecutive_failures = 0;
+            self.opened_at = None;
+            self.probing = false;
+            return;
+        }
+
+        self.consecutive_failures += 1;
+        let failed_probe = self.state == CircuitState::HalfOpen;
+        if failed_probe
+            || (self.state == CircuitState::Closed
+                && self.consecutive_failures >= *CIRCUIT_BREAKER_FAILURE_THRESHOLD)
+        {
+            self.state = CircuitState::Open;
+            self.opened_at = Some(now);
+            self.probing = false;
+            self.times_opened += 1;
+        }
+    }
+}
+
+// stops calling a failing node: after CIRCUIT_BREAKER_FAILURE_THRESHOLD consecutive failed
+// calls the breaker opens and refuses calls, after CIRCUIT_BREAKER_OPEN_SECS it lets a single
+// probe through, closing again when the probe succeeds and reopening when it fails
+#[derive(Debug, Clone, Default)]
+pub struct CircuitBreaker(Arc<Mutex<CircuitBreakerInner>>);
+impl CircuitBreaker {
+    // a permit to call the node, None when the call is refused
+    pub fn try_acquire(&self) -> Option<CircuitPermit> {
+        self.0
+            .lock()
+            .unwrap()
+            .try_acquire(Instant::now())
+            .then(|| CircuitPermit {
+                breaker: self.clone(),
+                recorded: false,
+            })
+    }
+
+    // while the breaker is not closed, trxs are served from the cache only
+    pub fn is_cache_only(&self) -> bool {
+        let mut inner = self.0.lock().unwrap();
+        inner.refresh(Instant::now());
+        inner.state != CircuitState::Closed
+    }
+
+    pub fn status(&self) -> CircuitBreakerStatus {
+        let mut inner = self.0.lock().unwrap();
+        inner.refresh(Instant::now());
+
+        CircuitBreakerStatus {
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            times_opened: inner.times_opened,
+            refused_calls: inner.refused_calls,
+        }
+    }
+}
+
+// an allowed node call, its outcome is recorded with record. A permit dropped before that,
+// e.g. by a cancelled request, releases the probe slot without counting as a failure
+#[derive(Debug)]
+pub struct CircuitPermit {
+    breaker: CircuitBreaker,
+    recorded: bool,
+}
+impl CircuitPermit {
+    pub fn record(mut self, success: bool) {
+        self.recorded = true;
+        self.breaker
+            .0
+            .lock()
+            .unwrap()
+            .record(success, Instant::now());
+    }
+}
+impl Drop for CircuitPermit {
+    fn drop(&mut self) {
+        if !self.recorded {
+            self.breaker.0.lock().unwrap().release();
+        }
+    }
+}
+
+// marks every response of a chain with an open breaker as served from the cache only
+pub async fn mark_cache_only(State(state): State<AppState>, mut response: Response) -> Response {
+    if state.circuit_breaker.is_cache_only() {
+        response.headers_mut().insert(
+            HeaderName::from_static("x-cache-only"),
+            HeaderValue::from_static("true"),
+        );
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_open_breaker(now: Instant) -> CircuitBreakerInner {
+        let mut breaker = CircuitBreakerInner::default();
+        for _ in 0..*CIRCUIT_BREAKER_FAILURE_THRESHOLD {
+            assert!(breaker.try_acquire(now));
+            breaker.record(false, now);
+        }
+        breaker
+    }
+
+    #[test]
+    fn test_opens_after_consecutive_failures() {
+        let now = Instant::now();
+        let mut breaker = CircuitBreakerInner::default();
+
+        // a success in between resets the count
+        breaker.record(false, now);
+        breaker.record(true, now);
+        assert_eq!(breaker.consecutive_failures, 0);
+
+        let mut breaker = fixture_open_breaker(now);
+        assert_eq!(breaker.state, CircuitState::Open);
+        assert_eq!(breaker.times_opened, 1);
+        assert!(!breaker.try_acquire(now));
+        assert_eq!(breaker.refused_calls, 1);
+    }
+
+    #[test]
+    fn test_half_open_lets_a_single_probe_through() {
+        let now = Instant::now();
+        let later = now + Duration::from_secs(*CIRCUIT_BREAKER_OPEN_SECS);
+        let mut breaker = fixture_open_breaker(now);
+
+        assert!(breaker.try_acquire(later));
+        assert_eq!(breaker.state, CircuitState::HalfOpen);
+        assert!(!breaker.try_acquire(later));
+
+        // a failed probe reopens the breaker
+        breaker.record(false, later);
+        assert_eq!(breaker.state, CircuitState::Open);
+        assert_eq!(breaker.times_opened, 2);
+
+        // a successful probe closes it
+        let latest = later + Duration::from_secs(*CIRCUIT_BREAKER_OPEN_SECS);
+        assert!(breaker.try_acquire(latest));
+        breaker.record(true, latest);
+        assert_eq!(breaker.state, CircuitState::Closed);
+        assert!(breaker.try_acquire(latest));
+    }
+
+    #[test]
+    fn test_dropped_probe_releases_the_half_open_breaker() {
+        let opened_at = Instant::now() - Duration::from_secs(*CIRCUIT_BREAKER_OPEN_SECS);
+        let breaker = CircuitBreaker(Arc::new(Mutex::new(fixture_open_breaker(opened_at))));
+
+        // the probe is cancelled before its outcome is recorded
+        let probe = breaker
+            .try_acquire()
+            .expect("The probe should be let through");
+        assert!(breaker.try_acquire().is_none());
+        drop(probe);
+
+        let probe = breaker
+            .try_acquire()
+            .expect("A new probe should be let through");
+        probe.record(true);
+        assert_eq!(breaker.status().state, CircuitState::Closed);
+    }
+}
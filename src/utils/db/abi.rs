@@ -0,0 +1,172 @@
+use sqlx::{Executor, Postgres};
+
+use crate::utils::{db::set_up::chain_id_migration, structs::abi::ContractAbi};
+
+pub async fn create_abis_table<'c, E>(executor: E, default_chain_id: i64) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "contract_abis" table exists,
+    // the same address can be a different contract on each chain
+    let query = format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS contract_abis (
+            chain_id BIGINT NOT NULL,
+            address TEXT NOT NULL,
+            abi JSONB NOT NULL,
+            uploaded_by TEXT NOT NULL,
+            PRIMARY KEY (chain_id, address)
+        );
+        {}
+        "#,
+        chain_id_migration("contract_abis", Some("chain_id, address"), default_chain_id),
+    );
+    executor.execute(query.as_str()).await?;
+
+    Ok(())
+}
+
+// saves the abi, an abi uploaded by another user is only replaced when replace_any is set.
+// Returns false when the abi was not saved
+pub async fn save_contract_abi<'c, E>(
+    executor: E,
+    chain_id: i64,
+    contract_abi: &ContractAbi,
+    replace_any: bool,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let result = sqlx::query(
+        r#"
+        INSERT INTO contract_abis (address, abi, uploaded_by, chain_id)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (chain_id, address) DO UPDATE SET
+            abi = EXCLUDED.abi,
+            uploaded_by = EXCLUDED.uploaded_by
+        WHERE $5 OR contract_abis.uploaded_by = EXCLUDED.uploaded_by
+        "#,
+    )
+    .bind(&contract_abi.address)
+    .bind(&contract_abi.abi)
+    .bind(&contract_abi.uploaded_by)
+    .bind(chain_id)
+    .bind(replace_any)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn fetch_contract_abis<'c, E>(
+    executor: E,
+    chain_id: i64,
+    addresses: Vec<String>,
+) -> Result<Vec<ContractAbi>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let abis = sqlx::query_as::<_, ContractAbi>(
+        r#"
+        SELECT address, abi, uploaded_by
+        FROM contract_abis
+        WHERE chain_id = $1 AND address = ANY($2)
+        "#,
+    )
+    .bind(chain_id)
+    .bind(addresses)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(abis)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::load_config;
+    use std::env;
+
+    use super::*;
+    use sqlx::{types::Json, Pool};
+
+    const CHAIN_ID: i64 = 11155111;
+
+    async fn fixture_pool() -> Pool<Postgres> {
+        load_config();
+
+        let database_url = env::var("DB_CONNECTION_URL").expect("DB_CONNECTION_URL must be set");
+        let pool = Pool::<Postgres>::connect(&database_url)
+            .await
+            .expect("Failed to connect to the database");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_save_and_fetch_contract_abis() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_abis_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create CONTRACT_ABIS table");
+
+        let contract_abi = ContractAbi {
+            address: "0xabc".to_string(),
+            abi: Json(serde_json::json!([])),
+            uploaded_by: "alice".to_string(),
+        };
+        let updated_abi = ContractAbi {
+            abi: Json(serde_json::json!([{ "type": "fallback" }])),
+            ..contract_abi.clone()
+        };
+        let replaced_abi = ContractAbi {
+            abi: Json(serde_json::json!([{ "type": "receive" }])),
+            uploaded_by: "bob".to_string(),
+            ..contract_abi.clone()
+        };
+
+        assert!(
+            save_contract_abi(&mut *db_trx, CHAIN_ID, &contract_abi, false)
+                .await
+                .expect("Failed to save contract abi")
+        );
+        // the uploader can update the abi, other users can not replace it
+        assert!(
+            save_contract_abi(&mut *db_trx, CHAIN_ID, &updated_abi, false)
+                .await
+                .expect("Failed to update contract abi")
+        );
+        assert!(
+            !save_contract_abi(&mut *db_trx, CHAIN_ID, &replaced_abi, false)
+                .await
+                .expect("Failed to update contract abi")
+        );
+
+        let abis = fetch_contract_abis(
+            &mut *db_trx,
+            CHAIN_ID,
+            vec!["0xabc".to_string(), "0xdef".to_string()],
+        )
+        .await
+        .expect("Failed to fetch contract abis");
+
+        assert_eq!(abis, vec![updated_abi]);
+
+        // an admin can replace it
+        assert!(
+            save_contract_abi(&mut *db_trx, CHAIN_ID, &replaced_abi, true)
+                .await
+                .expect("Failed to replace contract abi")
+        );
+        let abis = fetch_contract_abis(&mut *db_trx, CHAIN_ID, vec!["0xabc".to_string()])
+            .await
+            .expect("Failed to fetch contract abis");
+        assert_eq!(abis, vec![replaced_abi]);
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+}
@@ -0,0 +1,285 @@
+use sqlx::{Executor, Postgres};
+
+use crate::{
+    config::CACHE_TTL_SECS,
+    utils::{
+        db::set_up::chain_id_migration,
+        structs::{block::ResultBlock, reorg::CanonicalBlock, transaction::TransactionState},
+    },
+};
+
+pub async fn create_blocks_table<'c, E>(
+    executor: E,
+    default_chain_id: i64,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "blocks" table exists
+    let query = format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS blocks (
+            chain_id BIGINT NOT NULL,
+            block_hash TEXT NOT NULL,
+            block_number INTEGER NOT NULL,
+            parent_hash TEXT NOT NULL,
+            timestamp BIGINT NOT NULL,
+            base_fee_per_gas TEXT,
+            gas_used BIGINT NOT NULL,
+            gas_limit BIGINT NOT NULL,
+            miner TEXT NOT NULL,
+            transaction_hashes TEXT[] NOT NULL,
+            state TEXT NOT NULL,
+            cached_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (chain_id, block_hash)
+        );
+        {}
+        -- the index on the block number alone is replaced by one scoped to the chain
+        DROP INDEX IF EXISTS blocks_block_number_idx;
+        CREATE INDEX IF NOT EXISTS blocks_chain_block_number_idx ON blocks (chain_id, block_number);
+        "#,
+        chain_id_migration("blocks", Some("chain_id, block_hash"), default_chain_id),
+    );
+    executor.execute(query.as_str()).await?;
+
+    Ok(())
+}
+
+pub async fn save_block_to_db<'c, E>(
+    executor: E,
+    chain_id: i64,
+    block: &ResultBlock,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO blocks (
+            block_hash,
+            block_number,
+            parent_hash,
+            timestamp,
+            base_fee_per_gas,
+            gas_used,
+            gas_limit,
+            miner,
+            transaction_hashes,
+            state,
+            chain_id,
+            cached_at
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NOW())
+        ON CONFLICT (chain_id, block_hash) DO UPDATE SET
+            state = EXCLUDED.state,
+            cached_at = EXCLUDED.cached_at
+        "#,
+    )
+    .bind(&block.block_hash)
+    .bind(block.block_number)
+    .bind(&block.parent_hash)
+    .bind(block.timestamp)
+    .bind(&block.base_fee_per_gas)
+    .bind(block.gas_used)
+    .bind(block.gas_limit)
+    .bind(&block.miner)
+    .bind(&block.transaction_hashes)
+    .bind(block.state.as_str())
+    .bind(chain_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+// looks the block up by its hash or number, with the same expiry rules as cached trxs
+pub async fn check_block_in_db<'c, E>(
+    executor: E,
+    chain_id: i64,
+    block_hash: Option<&String>,
+    block_number: Option<i32>,
+) -> Result<Option<ResultBlock>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let block = sqlx::query_as::<_, ResultBlock>(
+        r#"
+        SELECT
+            block_hash,
+            block_number,
+            parent_hash,
+            timestamp,
+            base_fee_per_gas,
+            gas_used,
+            gas_limit,
+            miner,
+            transaction_hashes,
+            state
+        FROM blocks
+        WHERE chain_id = $5
+            AND (block_hash = $1 OR block_number = $2)
+            AND (state = ANY($3) OR cached_at > NOW() - make_interval(secs => $4))
+        ORDER BY cached_at DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(block_hash)
+    .bind(block_number)
+    .bind(TransactionState::immutable_states())
+    .bind(*CACHE_TTL_SECS as f64)
+    .bind(chain_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(block)
+}
+
+// the timestamp of a block hash never changes, so expired blocks are used as well
+pub async fn fetch_block_timestamps<'c, E>(
+    executor: E,
+    chain_id: i64,
+    block_hashes: Vec<String>,
+) -> Result<Vec<(String, i64)>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let timestamps = sqlx::query_as::<_, (String, i64)>(
+        r#"
+        SELECT block_hash, timestamp
+        FROM blocks
+        WHERE chain_id = $1 AND block_hash = ANY($2)
+        "#,
+    )
+    .bind(chain_id)
+    .bind(block_hashes)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(timestamps)
+}
+
+// removes the cached headers of the height which are not its canonical block
+pub async fn delete_non_canonical_blocks<'c, E>(
+    executor: E,
+    chain_id: i64,
+    block: &CanonicalBlock,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        DELETE FROM blocks
+        WHERE chain_id = $1 AND block_number = $2 AND block_hash <> $3
+        "#,
+    )
+    .bind(chain_id)
+    .bind(block.block_number)
+    .bind(&block.block_hash)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::load_config;
+    use std::env;
+
+    use super::*;
+    use sqlx::Pool;
+
+    const CHAIN_ID: i64 = 11155111;
+
+    async fn fixture_pool() -> Pool<Postgres> {
+        load_config();
+
+        let database_url = env::var("DB_CONNECTION_URL").expect("DB_CONNECTION_URL must be set");
+        let pool = Pool::<Postgres>::connect(&database_url)
+            .await
+            .expect("Failed to connect to the database");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_save_and_check_block_in_db() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_blocks_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create BLOCKS table");
+
+        let block = ResultBlock {
+            block_hash: "blockhash1".to_string(),
+            block_number: 100,
+            parent_hash: "blockhash0".to_string(),
+            timestamp: 1700000000,
+            base_fee_per_gas: Some("7".to_string()),
+            gas_used: 21000,
+            gas_limit: 30000000,
+            miner: "miner_address".to_string(),
+            transaction_hashes: vec!["hash1".to_string(), "hash2".to_string()],
+            state: TransactionState::Finalized,
+        };
+
+        save_block_to_db(&mut *db_trx, CHAIN_ID, &block)
+            .await
+            .expect("Failed to save block to db");
+
+        let by_hash = check_block_in_db(&mut *db_trx, CHAIN_ID, Some(&block.block_hash), None)
+            .await
+            .expect("Failed to check block by hash");
+        assert_eq!(by_hash, Some(block.clone()));
+
+        let by_number = check_block_in_db(&mut *db_trx, CHAIN_ID, None, Some(100))
+            .await
+            .expect("Failed to check block by number");
+        assert_eq!(by_number, Some(block.clone()));
+
+        let timestamps = fetch_block_timestamps(
+            &mut *db_trx,
+            CHAIN_ID,
+            vec![block.block_hash.clone(), "blockhash2".to_string()],
+        )
+        .await
+        .expect("Failed to fetch block timestamps");
+        assert_eq!(
+            timestamps,
+            vec![(block.block_hash.clone(), block.timestamp)]
+        );
+
+        // the canonical block of the height is kept
+        let canonical_block = CanonicalBlock {
+            block_number: 100,
+            block_hash: block.block_hash.clone(),
+        };
+        delete_non_canonical_blocks(&mut *db_trx, CHAIN_ID, &canonical_block)
+            .await
+            .expect("Failed to delete blocks");
+        let kept = check_block_in_db(&mut *db_trx, CHAIN_ID, None, Some(100))
+            .await
+            .expect("Failed to check kept block");
+        assert_eq!(kept, Some(block.clone()));
+
+        let reorged_block = CanonicalBlock {
+            block_number: 100,
+            block_hash: "blockhash2".to_string(),
+        };
+        delete_non_canonical_blocks(&mut *db_trx, CHAIN_ID, &reorged_block)
+            .await
+            .expect("Failed to delete blocks");
+
+        let deleted = check_block_in_db(&mut *db_trx, CHAIN_ID, None, Some(100))
+            .await
+            .expect("Failed to check deleted block");
+        assert_eq!(deleted, None);
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+}
@@ -0,0 +1,224 @@
+use sqlx::{Executor, Postgres};
+
+use crate::utils::{
+    db::set_up::{chain_id_migration, transaction_fk_migration},
+    structs::log::TransactionLog,
+};
+
+pub async fn create_logs_table<'c, E>(executor: E, default_chain_id: i64) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "logs" table exists
+    let query = format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS logs (
+            chain_id BIGINT NOT NULL,
+            transaction_hash TEXT NOT NULL,
+            log_index INTEGER NOT NULL,
+            address TEXT NOT NULL,
+            topics TEXT[] NOT NULL,
+            data TEXT NOT NULL,
+            removed BOOLEAN NOT NULL,
+            PRIMARY KEY (chain_id, transaction_hash, log_index),
+            FOREIGN KEY (chain_id, transaction_hash)
+                REFERENCES transactions (chain_id, transaction_hash) ON DELETE CASCADE
+        );
+        {}
+        {}
+        "#,
+        chain_id_migration(
+            "logs",
+            Some("chain_id, transaction_hash, log_index"),
+            default_chain_id
+        ),
+        transaction_fk_migration("logs"),
+    );
+    executor.execute(query.as_str()).await?;
+
+    Ok(())
+}
+
+pub async fn save_log_to_db<'c, E>(
+    executor: E,
+    chain_id: i64,
+    log: &TransactionLog,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO logs (
+            transaction_hash,
+            log_index,
+            address,
+            topics,
+            data,
+            removed,
+            chain_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (chain_id, transaction_hash, log_index) DO NOTHING
+        "#,
+    )
+    .bind(&log.transaction_hash)
+    .bind(log.log_index)
+    .bind(&log.address)
+    .bind(&log.topics)
+    .bind(&log.data)
+    .bind(log.removed)
+    .bind(chain_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_transaction_logs<'c, E>(
+    executor: E,
+    chain_id: i64,
+    trx_hash: &String,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        DELETE FROM logs
+        WHERE chain_id = $1 AND transaction_hash = $2
+        "#,
+    )
+    .bind(chain_id)
+    .bind(trx_hash)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn fetch_transaction_logs<'c, E>(
+    executor: E,
+    chain_id: i64,
+    trx_hash: &String,
+) -> Result<Vec<TransactionLog>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let logs = sqlx::query_as::<_, TransactionLog>(
+        r#"
+        SELECT
+            transaction_hash,
+            log_index,
+            address,
+            topics,
+            data,
+            removed
+        FROM logs
+        WHERE chain_id = $1 AND transaction_hash = $2
+        ORDER BY log_index
+        "#,
+    )
+    .bind(chain_id)
+    .bind(trx_hash)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(logs)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        load_config,
+        utils::{
+            db::transaction::{create_trx_table, save_transaction_to_db},
+            structs::transaction::{ResultTransaction, TransactionState},
+        },
+    };
+    use std::env;
+
+    use super::*;
+    use sqlx::Pool;
+
+    const CHAIN_ID: i64 = 11155111;
+
+    async fn fixture_pool() -> Pool<Postgres> {
+        load_config();
+
+        let database_url = env::var("DB_CONNECTION_URL").expect("DB_CONNECTION_URL must be set");
+        let pool = Pool::<Postgres>::connect(&database_url)
+            .await
+            .expect("Failed to connect to the database");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_save_and_fetch_transaction_logs() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_trx_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create TRANSACTIONS table");
+        create_logs_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create LOGS table");
+
+        let trx = ResultTransaction {
+            transaction_hash: "hash1".to_string(),
+            transaction_status: Some(1),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
+            from: "from_address".to_string(),
+            to: Some("to_address".to_string()),
+            contract_address: None,
+            logs_count: 2,
+            input: "input_data".to_string(),
+            value: "1000".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1700000000),
+            ..Default::default()
+        };
+
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx)
+            .await
+            .expect("Failed to save trx to db");
+
+        let log2 = TransactionLog {
+            transaction_hash: "hash1".to_string(),
+            log_index: 2,
+            address: "log_address".to_string(),
+            topics: vec!["topic0".to_string(), "topic1".to_string()],
+            data: "0x".to_string(),
+            removed: false,
+            decoded: None,
+            signature: None,
+        };
+        let log1 = TransactionLog {
+            log_index: 1,
+            topics: vec![],
+            ..log2.clone()
+        };
+
+        save_log_to_db(&mut *db_trx, CHAIN_ID, &log2)
+            .await
+            .expect("Failed to save log 2 in db");
+        save_log_to_db(&mut *db_trx, CHAIN_ID, &log1)
+            .await
+            .expect("Failed to save log 1 in db");
+
+        let logs = fetch_transaction_logs(&mut *db_trx, CHAIN_ID, &trx.transaction_hash)
+            .await
+            .expect("Failed to fetch transaction logs");
+
+        assert_eq!(logs, vec![log1, log2]);
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+}
@@ -1,3 +1,12 @@
+pub mod abi;
+pub mod block;
+pub mod log;
+pub mod reorg;
 pub mod set_up;
+pub mod signature;
+pub mod trace;
 pub mod transaction;
+pub mod transfer;
 pub mod user;
+pub mod watchlist;
+pub mod webhook;
@@ -0,0 +1,421 @@
+use sqlx::{Executor, Postgres};
+
+use crate::utils::{
+    db::set_up::chain_id_migration,
+    structs::reorg::{CanonicalBlock, ReorgEvent},
+};
+
+pub async fn create_canonical_blocks_table<'c, E>(
+    executor: E,
+    default_chain_id: i64,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "canonical_blocks" table exists
+    let query = format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS canonical_blocks (
+            chain_id BIGINT NOT NULL,
+            block_number INTEGER NOT NULL,
+            block_hash TEXT NOT NULL,
+            PRIMARY KEY (chain_id, block_number)
+        );
+        {}
+        "#,
+        chain_id_migration(
+            "canonical_blocks",
+            Some("chain_id, block_number"),
+            default_chain_id
+        ),
+    );
+    executor.execute(query.as_str()).await?;
+
+    Ok(())
+}
+
+pub async fn create_reorg_events_table<'c, E>(
+    executor: E,
+    default_chain_id: i64,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "reorg_events" table exists
+    let query = format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS reorg_events (
+            id SERIAL PRIMARY KEY,
+            chain_id BIGINT NOT NULL,
+            block_number INTEGER NOT NULL,
+            old_block_hash TEXT NOT NULL,
+            new_block_hash TEXT NOT NULL,
+            invalidated_transactions INTEGER NOT NULL,
+            detected_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        );
+        {}
+        "#,
+        chain_id_migration("reorg_events", None, default_chain_id),
+    );
+    executor.execute(query.as_str()).await?;
+
+    Ok(())
+}
+
+// records the canonical block hash of a height,
+// returns the previously recorded hash when it was different
+pub async fn save_canonical_block<'c, E>(
+    executor: E,
+    chain_id: i64,
+    block: &CanonicalBlock,
+) -> Result<Option<String>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let previous_hash: Option<String> = sqlx::query_scalar(
+        r#"
+        WITH previous AS (
+            SELECT block_hash FROM canonical_blocks WHERE chain_id = $3 AND block_number = $1
+        )
+        INSERT INTO canonical_blocks (block_number, block_hash, chain_id)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (chain_id, block_number) DO UPDATE SET block_hash = EXCLUDED.block_hash
+        RETURNING (SELECT block_hash FROM previous)
+        "#,
+    )
+    .bind(block.block_number)
+    .bind(&block.block_hash)
+    .bind(chain_id)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(previous_hash.filter(|previous_hash| *previous_hash != block.block_hash))
+}
+
+// the recorded canonical block hash of a height, None when the height was not checked yet
+pub async fn fetch_canonical_block_hash<'c, E>(
+    executor: E,
+    chain_id: i64,
+    block_number: i32,
+) -> Result<Option<String>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let block_hash: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT block_hash FROM canonical_blocks WHERE chain_id = $1 AND block_number = $2
+        "#,
+    )
+    .bind(chain_id)
+    .bind(block_number)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(block_hash)
+}
+
+// the heights between the two block numbers which have a recorded canonical block,
+// a cached trx or a cached block header, in ascending order
+pub async fn fetch_cached_block_numbers<'c, E>(
+    executor: E,
+    chain_id: i64,
+    from_block_number: i32,
+    to_block_number: i32,
+) -> Result<Vec<i32>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let block_numbers: Vec<i32> = sqlx::query_scalar(
+        r#"
+        SELECT block_number FROM canonical_blocks
+        WHERE chain_id = $1 AND block_number BETWEEN $2 AND $3
+        UNION
+        SELECT block_number FROM transactions
+        WHERE chain_id = $1 AND block_number BETWEEN $2 AND $3
+        UNION
+        SELECT block_number FROM blocks
+        WHERE chain_id = $1 AND block_number BETWEEN $2 AND $3
+        ORDER BY block_number
+        "#,
+    )
+    .bind(chain_id)
+    .bind(from_block_number)
+    .bind(to_block_number)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(block_numbers)
+}
+
+// removes the cached transactions of the height which are not in its canonical block,
+// their logs are removed by the cascading foreign key
+pub async fn invalidate_non_canonical_transactions<'c, E>(
+    executor: E,
+    chain_id: i64,
+    block: &CanonicalBlock,
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let result = sqlx::query(
+        r#"
+        DELETE FROM transactions
+        WHERE chain_id = $1 AND block_number = $2 AND block_hash <> $3
+        "#,
+    )
+    .bind(chain_id)
+    .bind(block.block_number)
+    .bind(&block.block_hash)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn save_reorg_event<'c, E>(
+    executor: E,
+    chain_id: i64,
+    block_number: i32,
+    old_block_hash: &String,
+    new_block_hash: &String,
+    invalidated_transactions: i32,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO reorg_events (
+            block_number,
+            old_block_hash,
+            new_block_hash,
+            invalidated_transactions,
+            chain_id
+        )
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(block_number)
+    .bind(old_block_hash)
+    .bind(new_block_hash)
+    .bind(invalidated_transactions)
+    .bind(chain_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn fetch_reorg_events<'c, E>(
+    executor: E,
+    chain_id: i64,
+    limit: i64,
+) -> Result<Vec<ReorgEvent>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let events = sqlx::query_as::<_, ReorgEvent>(
+        r#"
+        SELECT
+            id,
+            block_number,
+            old_block_hash,
+            new_block_hash,
+            invalidated_transactions,
+            EXTRACT(EPOCH FROM detected_at)::BIGINT AS detected_at
+        FROM reorg_events
+        WHERE chain_id = $1
+        ORDER BY id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(chain_id)
+    .bind(limit)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        load_config,
+        utils::{
+            db::{
+                block::create_blocks_table,
+                transaction::{check_transaction_in_db, create_trx_table, save_transaction_to_db},
+            },
+            structs::transaction::{ResultTransaction, TransactionState},
+        },
+    };
+    use std::env;
+
+    use super::*;
+    use sqlx::Pool;
+
+    const CHAIN_ID: i64 = 11155111;
+
+    async fn fixture_pool() -> Pool<Postgres> {
+        load_config();
+
+        let database_url = env::var("DB_CONNECTION_URL").expect("DB_CONNECTION_URL must be set");
+        let pool = Pool::<Postgres>::connect(&database_url)
+            .await
+            .expect("Failed to connect to the database");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_save_canonical_block() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_canonical_blocks_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create CANONICAL_BLOCKS table");
+
+        let block = CanonicalBlock {
+            block_number: 100,
+            block_hash: "blockhash1".to_string(),
+        };
+        let reorged_block = CanonicalBlock {
+            block_number: 100,
+            block_hash: "blockhash2".to_string(),
+        };
+
+        let first = save_canonical_block(&mut *db_trx, CHAIN_ID, &block)
+            .await
+            .expect("Failed to save canonical block");
+        let same = save_canonical_block(&mut *db_trx, CHAIN_ID, &block)
+            .await
+            .expect("Failed to save canonical block");
+        let reorged = save_canonical_block(&mut *db_trx, CHAIN_ID, &reorged_block)
+            .await
+            .expect("Failed to save reorged canonical block");
+
+        assert_eq!(first, None);
+        assert_eq!(same, None);
+        assert_eq!(reorged, Some("blockhash1".to_string()));
+
+        let block_hash = fetch_canonical_block_hash(&mut *db_trx, CHAIN_ID, 100)
+            .await
+            .expect("Failed to fetch canonical block");
+        assert_eq!(block_hash, Some(reorged_block.block_hash));
+        let unchecked = fetch_canonical_block_hash(&mut *db_trx, CHAIN_ID, 101)
+            .await
+            .expect("Failed to fetch canonical block");
+        assert_eq!(unchecked, None);
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_non_canonical_transactions() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_trx_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create TRANSACTIONS table");
+        create_blocks_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create BLOCKS table");
+        create_canonical_blocks_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create CANONICAL_BLOCKS table");
+        create_reorg_events_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create REORG_EVENTS table");
+
+        let trx = ResultTransaction {
+            transaction_hash: "hash1".to_string(),
+            transaction_status: Some(1),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
+            from: "from_address".to_string(),
+            to: Some("to_address".to_string()),
+            contract_address: None,
+            logs_count: 0,
+            input: "input_data".to_string(),
+            value: "1000".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1700000000),
+            ..Default::default()
+        };
+
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx)
+            .await
+            .expect("Failed to save trx to db");
+
+        save_canonical_block(
+            &mut *db_trx,
+            CHAIN_ID,
+            &CanonicalBlock {
+                block_number: 101,
+                block_hash: "blockhash3".to_string(),
+            },
+        )
+        .await
+        .expect("Failed to save canonical block");
+
+        // the heights of the cached trxs are checked as well as the recorded ones
+        let block_numbers = fetch_cached_block_numbers(&mut *db_trx, CHAIN_ID, 90, 200)
+            .await
+            .expect("Failed to fetch cached block numbers");
+        assert_eq!(block_numbers, vec![100, 101]);
+
+        // the trxs of the canonical block are kept
+        let block = CanonicalBlock {
+            block_number: 100,
+            block_hash: "blockhash1".to_string(),
+        };
+        let invalidated = invalidate_non_canonical_transactions(&mut *db_trx, CHAIN_ID, &block)
+            .await
+            .expect("Failed to invalidate block transactions");
+        assert_eq!(invalidated, 0);
+
+        let reorged_block = CanonicalBlock {
+            block_number: 100,
+            block_hash: "blockhash2".to_string(),
+        };
+        let invalidated =
+            invalidate_non_canonical_transactions(&mut *db_trx, CHAIN_ID, &reorged_block)
+                .await
+                .expect("Failed to invalidate block transactions");
+        assert_eq!(invalidated, 1);
+
+        let fetched_trx = check_transaction_in_db(&mut *db_trx, CHAIN_ID, &trx.transaction_hash)
+            .await
+            .unwrap();
+        assert!(fetched_trx.is_none());
+
+        save_reorg_event(
+            &mut *db_trx,
+            CHAIN_ID,
+            100,
+            &"blockhash1".to_string(),
+            &"blockhash2".to_string(),
+            invalidated as i32,
+        )
+        .await
+        .expect("Failed to save reorg event");
+
+        let events = fetch_reorg_events(&mut *db_trx, CHAIN_ID, 10)
+            .await
+            .expect("Failed to fetch reorg events");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].old_block_hash, "blockhash1");
+        assert_eq!(events[0].invalidated_transactions, 1);
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+}
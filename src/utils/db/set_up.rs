@@ -1,17 +1,77 @@
 use sqlx::{Pool, Postgres};
 
 use super::{
+    abi::create_abis_table,
+    block::create_blocks_table,
+    log::create_logs_table,
+    reorg::{create_canonical_blocks_table, create_reorg_events_table},
+    signature::create_signatures_table,
+    trace::create_traces_table,
     transaction::create_trx_table,
+    transfer::create_token_transfers_table,
     user::{create_users_table, seed_users},
+    watchlist::{create_indexer_cursors_table, create_watchlist_table},
+    webhook::{
+        create_tracked_transactions_table, create_webhook_deliveries_table, create_webhooks_table,
+    },
 };
 
-pub async fn init_db(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
+// rows cached before multi-chain support belong to the default chain
+pub async fn init_db(pool: &Pool<Postgres>, default_chain_id: i64) -> Result<(), sqlx::Error> {
     // Ensure the "transactions" table exists
-    create_trx_table(pool)
+    create_trx_table(pool, default_chain_id)
         .await
         .expect("Failed to create table TRANSACTIONS");
     println!("TRANSACTIONS table created");
 
+    // Ensure the "logs" table exists
+    create_logs_table(pool, default_chain_id)
+        .await
+        .expect("Failed to create table LOGS");
+    println!("LOGS table created");
+
+    // Ensure the "token_transfers" table exists
+    create_token_transfers_table(pool, default_chain_id)
+        .await
+        .expect("Failed to create table TOKEN_TRANSFERS");
+    println!("TOKEN_TRANSFERS table created");
+
+    // Ensure the "traces" table exists
+    create_traces_table(pool, default_chain_id)
+        .await
+        .expect("Failed to create table TRACES");
+    println!("TRACES table created");
+
+    // Ensure the "blocks" table exists
+    create_blocks_table(pool, default_chain_id)
+        .await
+        .expect("Failed to create table BLOCKS");
+    println!("BLOCKS table created");
+
+    // Ensure the "canonical_blocks" table exists
+    create_canonical_blocks_table(pool, default_chain_id)
+        .await
+        .expect("Failed to create table CANONICAL_BLOCKS");
+    println!("CANONICAL_BLOCKS table created");
+
+    // Ensure the "reorg_events" table exists
+    create_reorg_events_table(pool, default_chain_id)
+        .await
+        .expect("Failed to create table REORG_EVENTS");
+    println!("REORG_EVENTS table created");
+
+    // Ensure the "contract_abis" table exists
+    create_abis_table(pool, default_chain_id)
+        .await
+        .expect("Failed to create table CONTRACT_ABIS");
+    println!("CONTRACT_ABIS table created");
+
+    // Ensure the "signatures" table exists
+    create_signatures_table(pool)
+        .await
+        .expect("Failed to create table SIGNATURES");
+    println!("SIGNATURES table created");
+
     // Ensure the "users" table exists
     create_users_table(pool)
         .await
@@ -22,5 +82,88 @@ pub async fn init_db(pool: &Pool<Postgres>) -> Result<(), sqlx::Error> {
     seed_users(pool).await.expect("Failed to seed table USERS");
     println!("USERS table seeded");
 
+    // Ensure the "watchlist" table exists
+    create_watchlist_table(pool)
+        .await
+        .expect("Failed to create table WATCHLIST");
+    println!("WATCHLIST table created");
+
+    // Ensure the "indexer_cursors" table exists
+    create_indexer_cursors_table(pool)
+        .await
+        .expect("Failed to create table INDEXER_CURSORS");
+    println!("INDEXER_CURSORS table created");
+
+    // Ensure the "webhooks" table exists
+    create_webhooks_table(pool)
+        .await
+        .expect("Failed to create table WEBHOOKS");
+    println!("WEBHOOKS table created");
+
+    // Ensure the "tracked_transactions" table exists
+    create_tracked_transactions_table(pool)
+        .await
+        .expect("Failed to create table TRACKED_TRANSACTIONS");
+    println!("TRACKED_TRANSACTIONS table created");
+
+    // Ensure the "webhook_deliveries" table exists, after the "webhooks" table it references
+    create_webhook_deliveries_table(pool)
+        .await
+        .expect("Failed to create table WEBHOOK_DELIVERIES");
+    println!("WEBHOOK_DELIVERIES table created");
+
     Ok(())
 }
+
+// adds the chain_id column to a table created before multi-chain support,
+// existing rows get the default chain id and the primary key is extended once.
+// Dropping the old primary key also drops the foreign keys depending on it
+pub fn chain_id_migration(table: &str, primary_key: Option<&str>, default_chain_id: i64) -> String {
+    let mut migration = format!(
+        r#"
+        ALTER TABLE {table} ADD COLUMN IF NOT EXISTS chain_id BIGINT NOT NULL DEFAULT {default_chain_id};
+        ALTER TABLE {table} ALTER COLUMN chain_id DROP DEFAULT;
+        "#
+    );
+
+    if let Some(primary_key) = primary_key {
+        migration.push_str(&format!(
+            r#"
+            DO $$
+            BEGIN
+                IF NOT EXISTS (
+                    SELECT 1 FROM information_schema.key_column_usage
+                    WHERE table_name = '{table}'
+                        AND constraint_name = '{table}_pkey'
+                        AND column_name = 'chain_id'
+                ) THEN
+                    ALTER TABLE {table} DROP CONSTRAINT {table}_pkey CASCADE;
+                    ALTER TABLE {table} ADD PRIMARY KEY ({primary_key});
+                END IF;
+            END $$;
+            "#
+        ));
+    }
+
+    migration
+}
+
+// restores the foreign key to the transactions dropped by the chain_id_migration of transactions
+pub fn transaction_fk_migration(table: &str) -> String {
+    format!(
+        r#"
+        DO $$
+        BEGIN
+            IF NOT EXISTS (
+                SELECT 1 FROM information_schema.table_constraints
+                WHERE table_name = '{table}'
+                    AND constraint_name = '{table}_chain_id_transaction_hash_fkey'
+            ) THEN
+                ALTER TABLE {table} ADD CONSTRAINT {table}_chain_id_transaction_hash_fkey
+                    FOREIGN KEY (chain_id, transaction_hash)
+                    REFERENCES transactions (chain_id, transaction_hash) ON DELETE CASCADE;
+            END IF;
+        END $$;
+        "#
+    )
+}
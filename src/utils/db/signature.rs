@@ -0,0 +1,136 @@
+use sqlx::{Executor, Postgres};
+
+use crate::utils::structs::signature::TextSignature;
+
+pub async fn create_signatures_table<'c, E>(executor: E) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "signatures" table exists
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS signatures (
+            id SERIAL PRIMARY KEY,
+            selector TEXT NOT NULL,
+            signature TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            UNIQUE (selector, signature)
+        )
+        "#,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+// saves the signatures in a single statement, returns the number of newly saved ones
+pub async fn save_signatures<'c, E>(
+    executor: E,
+    signatures: &[TextSignature],
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let selectors: Vec<&str> = signatures.iter().map(|s| s.selector.as_str()).collect();
+    let texts: Vec<&str> = signatures.iter().map(|s| s.signature.as_str()).collect();
+    let kinds: Vec<&str> = signatures.iter().map(|s| s.kind.as_str()).collect();
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO signatures (selector, signature, kind)
+        SELECT * FROM UNNEST($1::TEXT[], $2::TEXT[], $3::TEXT[])
+        ON CONFLICT (selector, signature) DO NOTHING
+        "#,
+    )
+    .bind(selectors)
+    .bind(texts)
+    .bind(kinds)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// fetches the signatures of the selectors, in import order
+pub async fn fetch_signatures<'c, E>(
+    executor: E,
+    selectors: Vec<String>,
+) -> Result<Vec<TextSignature>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let signatures = sqlx::query_as::<_, TextSignature>(
+        r#"
+        SELECT selector, signature, kind
+        FROM signatures
+        WHERE selector = ANY($1)
+        ORDER BY id
+        "#,
+    )
+    .bind(selectors)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(signatures)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::load_config;
+    use std::env;
+
+    use super::*;
+    use sqlx::Pool;
+
+    async fn fixture_pool() -> Pool<Postgres> {
+        load_config();
+
+        let database_url = env::var("DB_CONNECTION_URL").expect("DB_CONNECTION_URL must be set");
+        let pool = Pool::<Postgres>::connect(&database_url)
+            .await
+            .expect("Failed to connect to the database");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_save_and_fetch_signatures() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_signatures_table(&mut *db_trx)
+            .await
+            .expect("Failed to create SIGNATURES table");
+
+        let signature1 = TextSignature {
+            selector: "0xtest0001".to_string(),
+            signature: "first(uint256)".to_string(),
+            kind: "function".to_string(),
+        };
+        let signature2 = TextSignature {
+            signature: "second(address)".to_string(),
+            ..signature1.clone()
+        };
+
+        let imported = save_signatures(&mut *db_trx, &[signature1.clone(), signature2.clone()])
+            .await
+            .expect("Failed to save signatures");
+        assert_eq!(imported, 2);
+
+        let imported = save_signatures(&mut *db_trx, std::slice::from_ref(&signature1))
+            .await
+            .expect("Failed to save duplicate signature");
+        assert_eq!(imported, 0);
+
+        let signatures = fetch_signatures(&mut *db_trx, vec!["0xtest0001".to_string()])
+            .await
+            .expect("Failed to fetch signatures");
+        assert_eq!(signatures, vec![signature1, signature2]);
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+}
@@ -0,0 +1,244 @@
+use sqlx::{Executor, Postgres};
+
+use crate::utils::{
+    db::set_up::{chain_id_migration, transaction_fk_migration},
+    structs::trace::TraceCall,
+};
+
+pub async fn create_traces_table<'c, E>(
+    executor: E,
+    default_chain_id: i64,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "traces" table exists
+    let query = format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS traces (
+            chain_id BIGINT NOT NULL,
+            transaction_hash TEXT NOT NULL,
+            trace_index INTEGER NOT NULL,
+            parent_index INTEGER,
+            depth INTEGER NOT NULL,
+            call_type TEXT NOT NULL,
+            "from" TEXT NOT NULL,
+            "to" TEXT,
+            value TEXT NOT NULL,
+            gas BIGINT NOT NULL,
+            gas_used BIGINT NOT NULL,
+            input TEXT NOT NULL,
+            output TEXT,
+            error TEXT,
+            revert_reason TEXT,
+            PRIMARY KEY (chain_id, transaction_hash, trace_index),
+            FOREIGN KEY (chain_id, transaction_hash)
+                REFERENCES transactions (chain_id, transaction_hash) ON DELETE CASCADE
+        );
+        {}
+        {}
+        "#,
+        chain_id_migration(
+            "traces",
+            Some("chain_id, transaction_hash, trace_index"),
+            default_chain_id
+        ),
+        transaction_fk_migration("traces"),
+    );
+    executor.execute(query.as_str()).await?;
+
+    Ok(())
+}
+
+pub async fn save_trace_call<'c, E>(
+    executor: E,
+    chain_id: i64,
+    call: &TraceCall,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO traces (
+            transaction_hash,
+            trace_index,
+            parent_index,
+            depth,
+            call_type,
+            "from",
+            "to",
+            value,
+            gas,
+            gas_used,
+            input,
+            output,
+            error,
+            revert_reason,
+            chain_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        ON CONFLICT (chain_id, transaction_hash, trace_index) DO NOTHING
+        "#,
+    )
+    .bind(&call.transaction_hash)
+    .bind(call.trace_index)
+    .bind(call.parent_index)
+    .bind(call.depth)
+    .bind(&call.call_type)
+    .bind(&call.from)
+    .bind(&call.to)
+    .bind(&call.value)
+    .bind(call.gas)
+    .bind(call.gas_used)
+    .bind(&call.input)
+    .bind(&call.output)
+    .bind(&call.error)
+    .bind(&call.revert_reason)
+    .bind(chain_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn fetch_transaction_trace<'c, E>(
+    executor: E,
+    chain_id: i64,
+    trx_hash: &String,
+) -> Result<Vec<TraceCall>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let calls = sqlx::query_as::<_, TraceCall>(
+        r#"
+        SELECT
+            transaction_hash,
+            trace_index,
+            parent_index,
+            depth,
+            call_type,
+            "from",
+            "to",
+            value,
+            gas,
+            gas_used,
+            input,
+            output,
+            error,
+            revert_reason
+        FROM traces
+        WHERE chain_id = $1 AND transaction_hash = $2
+        ORDER BY trace_index
+        "#,
+    )
+    .bind(chain_id)
+    .bind(trx_hash)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(calls)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        load_config,
+        utils::{
+            db::transaction::{create_trx_table, save_transaction_to_db},
+            structs::transaction::{ResultTransaction, TransactionState},
+        },
+    };
+    use std::env;
+
+    use super::*;
+    use sqlx::Pool;
+
+    const CHAIN_ID: i64 = 11155111;
+
+    async fn fixture_pool() -> Pool<Postgres> {
+        load_config();
+
+        let database_url = env::var("DB_CONNECTION_URL").expect("DB_CONNECTION_URL must be set");
+        let pool = Pool::<Postgres>::connect(&database_url)
+            .await
+            .expect("Failed to connect to the database");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_save_and_fetch_transaction_trace() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_trx_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create TRANSACTIONS table");
+        create_traces_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create TRACES table");
+
+        let trx = ResultTransaction {
+            transaction_hash: "hash1".to_string(),
+            transaction_status: Some(1),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
+            from: "from_address".to_string(),
+            to: Some("to_address".to_string()),
+            input: "input_data".to_string(),
+            value: "0".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1700000000),
+            ..Default::default()
+        };
+
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx)
+            .await
+            .expect("Failed to save trx to db");
+
+        let root_call = TraceCall {
+            transaction_hash: "hash1".to_string(),
+            trace_index: 0,
+            parent_index: None,
+            depth: 0,
+            call_type: "CALL".to_string(),
+            from: "from_address".to_string(),
+            to: Some("to_address".to_string()),
+            value: "0".to_string(),
+            gas: 50000,
+            gas_used: 30000,
+            input: "0x".to_string(),
+            output: Some("0x".to_string()),
+            error: None,
+            revert_reason: None,
+        };
+        let inner_call = TraceCall {
+            trace_index: 1,
+            parent_index: Some(0),
+            depth: 1,
+            call_type: "DELEGATECALL".to_string(),
+            error: Some("execution reverted".to_string()),
+            ..root_call.clone()
+        };
+
+        save_trace_call(&mut *db_trx, CHAIN_ID, &inner_call)
+            .await
+            .expect("Failed to save inner call");
+        save_trace_call(&mut *db_trx, CHAIN_ID, &root_call)
+            .await
+            .expect("Failed to save root call");
+
+        let calls = fetch_transaction_trace(&mut *db_trx, CHAIN_ID, &trx.transaction_hash)
+            .await
+            .expect("Failed to fetch trace");
+
+        assert_eq!(calls, vec![root_call, inner_call]);
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+}
@@ -1,9 +1,19 @@
 use sqlx::{Executor, Postgres};
 
-use crate::utils::structs::transaction::ResultTransaction;
+use crate::{
+    config::CACHE_TTL_SECS,
+    utils::{
+        db::set_up::chain_id_migration,
+        structs::{
+            address::AddressDirection,
+            transaction::{ResultTransaction, TransactionState},
+        },
+    },
+};
 
 pub async fn save_transaction_to_db<'c, E>(
     executor: E,
+    chain_id: i64,
     trx: &ResultTransaction,
 ) -> Result<(), sqlx::Error>
 where
@@ -22,21 +32,65 @@ where
             contract_address,
             logs_count,
             input,
-            value
+            value,
+            state,
+            transaction_type,
+            nonce,
+            gas_limit,
+            gas_used,
+            effective_gas_price,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            access_list,
+            blob_versioned_hashes,
+            max_fee_per_blob_gas,
+            authorization_list,
+            block_timestamp,
+            chain_id,
+            cached_at
         )
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        VALUES (
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11,
+            $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22,
+            $23, $24, NOW()
+        )
+        ON CONFLICT (chain_id, transaction_hash) DO UPDATE SET
+            transaction_status = EXCLUDED.transaction_status,
+            block_hash = EXCLUDED.block_hash,
+            block_number = EXCLUDED.block_number,
+            block_timestamp = EXCLUDED.block_timestamp,
+            contract_address = EXCLUDED.contract_address,
+            logs_count = EXCLUDED.logs_count,
+            state = EXCLUDED.state,
+            gas_used = EXCLUDED.gas_used,
+            effective_gas_price = EXCLUDED.effective_gas_price,
+            cached_at = EXCLUDED.cached_at
         "#,
     )
     .bind(trx.transaction_hash.clone())
-    .bind(trx.transaction_status.clone() as i16)
+    .bind(trx.transaction_status)
     .bind(trx.block_hash.clone())
-    .bind(trx.block_number.clone() as i32)
+    .bind(trx.block_number)
     .bind(trx.from.clone())
     .bind(trx.to.clone().map(|val| val.to_string()))
     .bind(trx.contract_address.clone())
-    .bind(trx.logs_count.clone() as i32)
+    .bind(trx.logs_count)
     .bind(trx.input.clone())
     .bind(trx.value.clone().to_string())
+    .bind(trx.state.as_str())
+    .bind(trx.transaction_type)
+    .bind(trx.nonce)
+    .bind(trx.gas_limit)
+    .bind(trx.gas_used)
+    .bind(&trx.effective_gas_price)
+    .bind(&trx.max_fee_per_gas)
+    .bind(&trx.max_priority_fee_per_gas)
+    .bind(&trx.access_list)
+    .bind(&trx.blob_versioned_hashes)
+    .bind(&trx.max_fee_per_blob_gas)
+    .bind(&trx.authorization_list)
+    .bind(trx.block_timestamp)
+    .bind(chain_id)
     .execute(executor)
     .await?;
 
@@ -45,7 +99,22 @@ where
 
 pub async fn check_transaction_in_db<'c, E>(
     executor: E,
+    chain_id: i64,
+    trx_hash: &String,
+) -> Result<Option<ResultTransaction>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    check_transaction_in_cache(executor, chain_id, trx_hash, false).await
+}
+
+// with include_stale the trx is returned even when its ttl expired,
+// used to serve the cache only while the node is unavailable
+pub async fn check_transaction_in_cache<'c, E>(
+    executor: E,
+    chain_id: i64,
     trx_hash: &String,
+    include_stale: bool,
 ) -> Result<Option<ResultTransaction>, sqlx::Error>
 where
     E: Executor<'c, Database = Postgres>,
@@ -57,32 +126,82 @@ where
             transaction_status,
             block_hash,
             block_number,
+            block_timestamp,
             "from",
             "to",
             contract_address,
             logs_count,
             input,
-            value
+            value,
+            state,
+            transaction_type,
+            nonce,
+            gas_limit,
+            gas_used,
+            effective_gas_price,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            access_list,
+            blob_versioned_hashes,
+            max_fee_per_blob_gas,
+            authorization_list,
+            revert_reason,
+            revert_data
         FROM transactions
-        WHERE transaction_hash = $1
+        WHERE chain_id = $1 AND transaction_hash = $2
+            AND ($5 OR state = ANY($3) OR cached_at > NOW() - make_interval(secs => $4))
+            -- rows cached before the typed trx fields existed are fetched again,
+            -- a missing block timestamp is filled in on read
+            AND transaction_type IS NOT NULL
         "#,
     )
+    .bind(chain_id)
     .bind(trx_hash) // Bind the transaction hash parameter
+    .bind(TransactionState::immutable_states()) // immutable trxs never expire
+    .bind(*CACHE_TTL_SECS as f64) // younger trxs are revalidated after the ttl
+    .bind(include_stale)
     .fetch_optional(executor) // Fetch the result as an Option<ResultTransaction>
     .await?;
 
-    Ok(transaction)
+    Ok(transaction.map(ResultTransaction::with_block_time))
 }
 
-pub async fn create_trx_table<'c, E>(executor: E) -> Result<(), sqlx::Error>
+// saves the block timestamp resolved for a trx cached without it
+pub async fn save_transaction_block_timestamp<'c, E>(
+    executor: E,
+    chain_id: i64,
+    trx_hash: &String,
+    block_timestamp: i64,
+) -> Result<(), sqlx::Error>
 where
     E: Executor<'c, Database = Postgres>,
 {
-    // Ensure the "transactions" table exists
     sqlx::query(
+        r#"
+        UPDATE transactions SET block_timestamp = $3
+        WHERE chain_id = $1 AND transaction_hash = $2
+        "#,
+    )
+    .bind(chain_id)
+    .bind(trx_hash)
+    .bind(block_timestamp)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn create_trx_table<'c, E>(executor: E, default_chain_id: i64) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "transactions" table exists,
+    // columns added later are also created on existing tables
+    let query = format!(
         r#"
         CREATE TABLE IF NOT EXISTS transactions (
-            transaction_hash TEXT PRIMARY KEY,
+            chain_id BIGINT NOT NULL,
+            transaction_hash TEXT NOT NULL,
             transaction_status SMALLINT NOT NULL,
             block_hash TEXT NOT NULL,
             block_number INTEGER NOT NULL,
@@ -91,19 +210,101 @@ where
             contract_address TEXT,
             logs_count INTEGER NOT NULL,
             input TEXT NOT NULL,
-            value TEXT NOT NULL
-        )
+            value TEXT NOT NULL,
+            PRIMARY KEY (chain_id, transaction_hash)
+        );
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS state TEXT NOT NULL DEFAULT 'latest';
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS cached_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS transaction_type SMALLINT;
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS nonce BIGINT;
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS gas_limit BIGINT;
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS gas_used BIGINT;
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS effective_gas_price TEXT;
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS max_fee_per_gas TEXT;
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS max_priority_fee_per_gas TEXT;
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS access_list JSONB;
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS blob_versioned_hashes TEXT[];
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS max_fee_per_blob_gas TEXT;
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS authorization_list JSONB;
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS revert_reason TEXT;
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS revert_data TEXT;
+        ALTER TABLE transactions ADD COLUMN IF NOT EXISTS block_timestamp BIGINT;
+        {chain_id_migration}
+        CREATE INDEX IF NOT EXISTS transactions_block_timestamp_idx ON transactions (block_timestamp);
+        CREATE INDEX IF NOT EXISTS transactions_block_number_idx ON transactions (chain_id, block_number);
+        CREATE INDEX IF NOT EXISTS transactions_from_idx ON transactions (LOWER("from"), block_number DESC, transaction_hash DESC);
+        CREATE INDEX IF NOT EXISTS transactions_to_idx ON transactions (LOWER("to"), block_number DESC, transaction_hash DESC);
+        CREATE INDEX IF NOT EXISTS transactions_contract_address_idx ON transactions (LOWER(contract_address), block_number DESC, transaction_hash DESC);
+        "#,
+        chain_id_migration = chain_id_migration(
+            "transactions",
+            Some("chain_id, transaction_hash"),
+            default_chain_id
+        ),
+    );
+    executor.execute(query.as_str()).await.unwrap();
+
+    Ok(())
+}
+
+// the revert data is only set once the revert reason was resolved,
+// so the columns are not touched when the trx is saved again
+pub async fn save_revert_reason<'c, E>(
+    executor: E,
+    chain_id: i64,
+    trx_hash: &String,
+    revert_reason: &Option<String>,
+    revert_data: &String,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        UPDATE transactions
+        SET revert_reason = $3, revert_data = $4
+        WHERE chain_id = $1 AND transaction_hash = $2
         "#,
     )
+    .bind(chain_id)
+    .bind(trx_hash)
+    .bind(revert_reason)
+    .bind(revert_data)
     .execute(executor)
-    .await
-    .unwrap();
+    .await?;
 
     Ok(())
 }
 
+pub async fn fetch_revert_reason<'c, E>(
+    executor: E,
+    chain_id: i64,
+    trx_hash: &String,
+) -> Result<Option<(Option<String>, String)>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let revert_reason = sqlx::query_as::<_, (Option<String>, String)>(
+        r#"
+        SELECT revert_reason, revert_data
+        FROM transactions
+        WHERE chain_id = $1 AND transaction_hash = $2 AND revert_data IS NOT NULL
+        "#,
+    )
+    .bind(chain_id)
+    .bind(trx_hash)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(revert_reason)
+}
+
+// from and to are optional inclusive bounds of the block timestamp
 pub async fn fetch_all_transactions<'c, E>(
     executor: E,
+    chain_id: i64,
+    from: Option<i64>,
+    to: Option<i64>,
 ) -> Result<Vec<ResultTransaction>, sqlx::Error>
 where
     E: Executor<'c, Database = Postgres>,
@@ -115,24 +316,51 @@ where
             transaction_status,
             block_hash,
             block_number,
+            block_timestamp,
             "from",
             "to",
             contract_address,
             logs_count,
             input,
-            value
+            value,
+            state,
+            transaction_type,
+            nonce,
+            gas_limit,
+            gas_used,
+            effective_gas_price,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            access_list,
+            blob_versioned_hashes,
+            max_fee_per_blob_gas,
+            authorization_list,
+            revert_reason,
+            revert_data
         FROM transactions
+        WHERE chain_id = $1
+            AND ($2::BIGINT IS NULL OR block_timestamp >= $2)
+            AND ($3::BIGINT IS NULL OR block_timestamp <= $3)
         "#,
     )
+    .bind(chain_id)
+    .bind(from)
+    .bind(to)
     .fetch_all(executor) // Fetch the result as an Option<ResultTransaction>
     .await?;
 
-    Ok(transaction)
+    Ok(transaction
+        .into_iter()
+        .map(ResultTransaction::with_block_time)
+        .collect())
 }
 
 pub async fn fetch_matching_transactions<'c, E>(
     pool: E,
+    chain_id: i64,
     transaction_hashes: Vec<String>,
+    from: Option<i64>,
+    to: Option<i64>,
 ) -> Result<Vec<ResultTransaction>, sqlx::Error>
 where
     E: Executor<'c, Database = Postgres>,
@@ -148,8 +376,15 @@ where
         r#"
         SELECT * FROM transactions
         WHERE transaction_hash = ANY(ARRAY[{}]::TEXT[])
+            AND (${from}::BIGINT IS NULL OR block_timestamp >= ${from})
+            AND (${to}::BIGINT IS NULL OR block_timestamp <= ${to})
+            AND chain_id = ${chain_id}
+        ORDER BY block_number, transaction_hash
         "#,
-        placeholders.join(", ")
+        placeholders.join(", "),
+        from = transaction_hashes.len() + 1,
+        to = transaction_hashes.len() + 2,
+        chain_id = transaction_hashes.len() + 3,
     );
 
     // bind all trx hashes
@@ -157,19 +392,75 @@ where
     for hash in transaction_hashes {
         query_builder = query_builder.bind(hash);
     }
+    query_builder = query_builder.bind(from).bind(to).bind(chain_id);
 
     let transactions = query_builder.fetch_all(pool).await?;
 
-    Ok(transactions)
+    Ok(transactions
+        .into_iter()
+        .map(ResultTransaction::with_block_time)
+        .collect())
+}
+
+// the address is matched lowercase against the indexed LOWER(...) columns,
+// the trxs are ordered newest first and the cursor is the last trx of the previous page
+pub async fn fetch_address_transactions<'c, E>(
+    executor: E,
+    chain_id: i64,
+    address: &String,
+    direction: Option<AddressDirection>,
+    cursor: Option<(i32, String)>,
+    limit: i64,
+) -> Result<Vec<ResultTransaction>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let address_filter = match direction {
+        Some(AddressDirection::Sent) => r#"LOWER("from") = $1"#,
+        Some(AddressDirection::Received) => r#"LOWER("to") = $1"#,
+        Some(AddressDirection::Created) => r#"LOWER(contract_address) = $1"#,
+        None => r#"(LOWER("from") = $1 OR LOWER("to") = $1 OR LOWER(contract_address) = $1)"#,
+    };
+
+    let query = format!(
+        r#"
+        SELECT * FROM transactions
+        WHERE chain_id = $5 AND {}
+            AND ($2::INTEGER IS NULL OR (block_number, transaction_hash) < ($2, $3))
+        ORDER BY block_number DESC, transaction_hash DESC
+        LIMIT $4
+        "#,
+        address_filter
+    );
+
+    let (cursor_block_number, cursor_hash) = cursor.unzip();
+    let transactions = sqlx::query_as::<_, ResultTransaction>(&query)
+        .bind(address)
+        .bind(cursor_block_number)
+        .bind(cursor_hash)
+        .bind(limit)
+        .bind(chain_id)
+        .fetch_all(executor)
+        .await?;
+
+    Ok(transactions
+        .into_iter()
+        .map(ResultTransaction::with_block_time)
+        .collect())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::load_config;
+    use crate::{
+        load_config,
+        utils::address::{decode_cursor, encode_cursor},
+    };
     use std::env;
 
     use super::*;
-    use sqlx::Pool;
+    use sqlx::{types::Json, Pool};
+
+    const CHAIN_ID: i64 = 11155111;
 
     async fn fixture_pool() -> Pool<Postgres> {
         load_config();
@@ -187,28 +478,32 @@ mod tests {
         let pool = fixture_pool().await;
         let mut db_trx = pool.begin().await.expect("Pool transaction failed");
 
-        create_trx_table(&mut *db_trx)
+        create_trx_table(&mut *db_trx, CHAIN_ID)
             .await
             .expect("Failed to create TRANSACTIONS table");
 
         let trx = ResultTransaction {
             transaction_hash: "hash1".to_string(),
-            transaction_status: 1,
-            block_hash: "blockhash1".to_string(),
-            block_number: 100,
+            transaction_status: Some(1),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
             from: "from_address".to_string(),
             to: Some("to_address".to_string()),
             contract_address: None,
             logs_count: 10,
             input: "input_data".to_string(),
             value: "1000".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1700000000),
+            ..Default::default()
         };
 
-        save_transaction_to_db(&mut *db_trx, &trx)
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx)
             .await
             .expect("Failed to save trx to db");
 
-        let fetched_trx = check_transaction_in_db(&mut *db_trx, &trx.transaction_hash)
+        let fetched_trx = check_transaction_in_db(&mut *db_trx, CHAIN_ID, &trx.transaction_hash)
             .await
             .unwrap();
 
@@ -221,54 +516,133 @@ mod tests {
             .expect("Failed to rollback test trx");
     }
 
+    #[tokio::test]
+    async fn test_trx_without_block_timestamp() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_trx_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create TRANSACTIONS table");
+
+        let trx = ResultTransaction {
+            transaction_hash: "hash1".to_string(),
+            transaction_status: Some(1),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
+            from: "from_address".to_string(),
+            input: "input_data".to_string(),
+            value: "1000".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: None,
+            ..Default::default()
+        };
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx)
+            .await
+            .expect("Failed to save trx to db");
+
+        // the trx is still a cache hit, its timestamp is filled in later
+        let fetched_trx = check_transaction_in_db(&mut *db_trx, CHAIN_ID, &trx.transaction_hash)
+            .await
+            .expect("Failed to check trx in db");
+        assert_eq!(fetched_trx.map(|trx| trx.block_timestamp), Some(None));
+
+        save_transaction_block_timestamp(&mut *db_trx, CHAIN_ID, &trx.transaction_hash, 1700000000)
+            .await
+            .expect("Failed to save block timestamp");
+        let fetched_trx = check_transaction_in_db(&mut *db_trx, CHAIN_ID, &trx.transaction_hash)
+            .await
+            .expect("Failed to check trx in db");
+        assert_eq!(
+            fetched_trx,
+            Some(
+                ResultTransaction {
+                    block_timestamp: Some(1700000000),
+                    ..trx
+                }
+                .with_block_time()
+            )
+        );
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+
     #[tokio::test]
     async fn test_fetch_all_transactions() {
         let pool = fixture_pool().await;
         let mut db_trx = pool.begin().await.expect("Pool transaction failed");
 
-        create_trx_table(&mut *db_trx)
+        create_trx_table(&mut *db_trx, CHAIN_ID)
             .await
             .expect("Failed to create TRANSACTIONS table");
 
         let trx1 = ResultTransaction {
             transaction_hash: "hash1".to_string(),
-            transaction_status: 1,
-            block_hash: "blockhash1".to_string(),
-            block_number: 100,
+            transaction_status: Some(1),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
             from: "from_address".to_string(),
             to: Some("to_address".to_string()),
             contract_address: None,
             logs_count: 10,
             input: "input_data".to_string(),
             value: "1000".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1700000000),
+            ..Default::default()
         };
 
         let trx2 = ResultTransaction {
             transaction_hash: "hash2".to_string(),
-            transaction_status: 2,
-            block_hash: "blockhash2".to_string(),
-            block_number: 200,
+            transaction_status: Some(2),
+            block_hash: Some("blockhash2".to_string()),
+            block_number: Some(200),
             from: "from_address2".to_string(),
             to: None,
             contract_address: None,
             logs_count: 5,
             input: "input_data2".to_string(),
             value: "2000".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1800000000),
+            ..Default::default()
         };
 
-        save_transaction_to_db(&mut *db_trx, &trx1)
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx1)
             .await
             .expect("Failed to save db_trx 1 in db");
-        save_transaction_to_db(&mut *db_trx, &trx2)
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx2)
             .await
             .expect("Failed to save db_trx 2 in db");
 
-        let transactions = fetch_all_transactions(&mut *db_trx)
+        let transactions = fetch_all_transactions(&mut *db_trx, CHAIN_ID, None, None)
             .await
             .expect("Failed to fetch all transactions");
 
-        assert_eq!(transactions.contains(&trx1), true);
-        assert_eq!(transactions.contains(&trx2), true);
+        let trx1 = trx1.with_block_time();
+        let trx2 = trx2.with_block_time();
+        assert!(transactions.contains(&trx1));
+        assert!(transactions.contains(&trx2));
+
+        let transactions = fetch_all_transactions(&mut *db_trx, CHAIN_ID, Some(1750000000), None)
+            .await
+            .expect("Failed to fetch transactions from date");
+
+        assert!(!transactions.contains(&trx1));
+        assert!(transactions.contains(&trx2));
+
+        let transactions = fetch_all_transactions(&mut *db_trx, CHAIN_ID, None, Some(1750000000))
+            .await
+            .expect("Failed to fetch transactions to date");
+
+        assert!(transactions.contains(&trx1));
+        assert!(!transactions.contains(&trx2));
     }
 
     #[tokio::test]
@@ -276,50 +650,430 @@ mod tests {
         let pool = fixture_pool().await;
         let mut db_trx = pool.begin().await.expect("Pool transaction failed");
 
-        create_trx_table(&mut *db_trx)
+        create_trx_table(&mut *db_trx, CHAIN_ID)
             .await
             .expect("Failed to create TRANSACTIONS table");
 
         let trx1 = ResultTransaction {
             transaction_hash: "hash1".to_string(),
-            transaction_status: 1,
-            block_hash: "blockhash1".to_string(),
-            block_number: 100,
+            transaction_status: Some(1),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
             from: "from_address".to_string(),
             to: Some("to_address".to_string()),
             contract_address: None,
             logs_count: 10,
             input: "input_data".to_string(),
             value: "1000".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1700000000),
+            ..Default::default()
         };
 
         let trx2 = ResultTransaction {
             transaction_hash: "hash2".to_string(),
-            transaction_status: 2,
-            block_hash: "blockhash2".to_string(),
-            block_number: 200,
+            transaction_status: Some(2),
+            block_hash: Some("blockhash2".to_string()),
+            block_number: Some(200),
             from: "from_address2".to_string(),
             to: None,
             contract_address: None,
             logs_count: 5,
             input: "input_data2".to_string(),
             value: "2000".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1700000000),
+            ..Default::default()
         };
 
-        save_transaction_to_db(&mut *db_trx, &trx1)
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx1)
             .await
             .expect("Failed to save db_trx 1 in db");
-        save_transaction_to_db(&mut *db_trx, &trx2)
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx2)
             .await
             .expect("Failed to save db_trx 2 in db");
 
         let transaction_hashes = vec![trx1.transaction_hash.clone(), trx2.transaction_hash.clone()];
-        let transactions = fetch_matching_transactions(&mut *db_trx, transaction_hashes)
-            .await
-            .expect("Failed to fetch matching transactions");
+        let transactions =
+            fetch_matching_transactions(&mut *db_trx, CHAIN_ID, transaction_hashes, None, None)
+                .await
+                .expect("Failed to fetch matching transactions");
 
         assert_eq!(transactions.len(), 2);
         assert_eq!(transactions[0].transaction_hash, trx1.transaction_hash);
         assert_eq!(transactions[1].transaction_hash, trx2.transaction_hash);
     }
+
+    #[tokio::test]
+    async fn test_check_transaction_in_db_revalidates_expired() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_trx_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create TRANSACTIONS table");
+
+        let latest_trx = ResultTransaction {
+            transaction_hash: "hash1".to_string(),
+            transaction_status: Some(1),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
+            from: "from_address".to_string(),
+            to: Some("to_address".to_string()),
+            contract_address: None,
+            logs_count: 10,
+            input: "input_data".to_string(),
+            value: "1000".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1700000000),
+            ..Default::default()
+        };
+        let finalized_trx = ResultTransaction {
+            transaction_hash: "hash2".to_string(),
+            state: TransactionState::Finalized,
+            ..latest_trx.clone()
+        };
+
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &latest_trx)
+            .await
+            .expect("Failed to save latest trx in db");
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &finalized_trx)
+            .await
+            .expect("Failed to save finalized trx in db");
+
+        // expire both trxs
+        sqlx::query("UPDATE transactions SET cached_at = NOW() - INTERVAL '1 day'")
+            .execute(&mut *db_trx)
+            .await
+            .expect("Failed to expire trxs");
+
+        let fetched_latest =
+            check_transaction_in_db(&mut *db_trx, CHAIN_ID, &latest_trx.transaction_hash)
+                .await
+                .unwrap();
+        let fetched_finalized =
+            check_transaction_in_db(&mut *db_trx, CHAIN_ID, &finalized_trx.transaction_hash)
+                .await
+                .unwrap();
+
+        assert!(fetched_latest.is_none());
+        assert_eq!(fetched_finalized, Some(finalized_trx.with_block_time()));
+
+        // the expired trx is still served when the stale cache is asked for
+        let stale_latest =
+            check_transaction_in_cache(&mut *db_trx, CHAIN_ID, &latest_trx.transaction_hash, true)
+                .await
+                .unwrap();
+        assert_eq!(stale_latest, Some(latest_trx.with_block_time()));
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+
+    #[tokio::test]
+    async fn test_save_typed_transaction_fields() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_trx_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create TRANSACTIONS table");
+
+        let trx = ResultTransaction {
+            transaction_hash: "hash1".to_string(),
+            transaction_status: Some(1),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
+            from: "from_address".to_string(),
+            to: Some("to_address".to_string()),
+            contract_address: None,
+            logs_count: 0,
+            input: "input_data".to_string(),
+            value: "1000".to_string(),
+            state: TransactionState::Finalized,
+            transaction_type: Some(3),
+            block_timestamp: Some(1700000000),
+            nonce: Some(7),
+            gas_limit: Some(21000),
+            gas_used: Some(21000),
+            effective_gas_price: Some("1000000000".to_string()),
+            max_fee_per_gas: Some("2000000000".to_string()),
+            max_priority_fee_per_gas: Some("1000000".to_string()),
+            access_list: Some(Json(serde_json::json!([
+                { "address": "0x01", "storageKeys": ["0x02"] }
+            ]))),
+            blob_versioned_hashes: Some(vec!["blobhash1".to_string()]),
+            max_fee_per_blob_gas: Some("3".to_string()),
+            authorization_list: None,
+            ..Default::default()
+        };
+
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx)
+            .await
+            .expect("Failed to save trx to db");
+
+        let fetched_trx = check_transaction_in_db(&mut *db_trx, CHAIN_ID, &trx.transaction_hash)
+            .await
+            .unwrap();
+
+        assert_eq!(fetched_trx, Some(trx.with_block_time()));
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+
+    #[tokio::test]
+    async fn test_save_revert_reason_survives_resave() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_trx_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create TRANSACTIONS table");
+
+        let trx = ResultTransaction {
+            transaction_hash: "hash1".to_string(),
+            transaction_status: Some(0),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
+            from: "from_address".to_string(),
+            input: "input_data".to_string(),
+            value: "0".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1700000000),
+            ..Default::default()
+        };
+
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx)
+            .await
+            .expect("Failed to save trx to db");
+
+        let unresolved = fetch_revert_reason(&mut *db_trx, CHAIN_ID, &trx.transaction_hash)
+            .await
+            .expect("Failed to fetch revert reason");
+        assert_eq!(unresolved, None);
+
+        save_revert_reason(
+            &mut *db_trx,
+            CHAIN_ID,
+            &trx.transaction_hash,
+            &Some("Insufficient balance".to_string()),
+            &"0x08c379a0".to_string(),
+        )
+        .await
+        .expect("Failed to save revert reason");
+
+        // saving the refetched trx keeps the resolved revert reason
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx)
+            .await
+            .expect("Failed to save trx to db");
+
+        let resolved = fetch_revert_reason(&mut *db_trx, CHAIN_ID, &trx.transaction_hash)
+            .await
+            .expect("Failed to fetch revert reason");
+        assert_eq!(
+            resolved,
+            Some((
+                Some("Insufficient balance".to_string()),
+                "0x08c379a0".to_string()
+            ))
+        );
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_address_transactions() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_trx_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create TRANSACTIONS table");
+
+        let address = "0x5520a8a1723Fdc8a8e64Da1f348CC1991C13C1C3".to_string();
+        let sent = ResultTransaction {
+            transaction_hash: "address_hash1".to_string(),
+            transaction_status: Some(1),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
+            from: address.clone(),
+            to: Some("0xea58fcA6849d79EAd1f26608855c2D6407d54Ce2".to_string()),
+            input: "0x".to_string(),
+            value: "0".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1700000000),
+            ..Default::default()
+        };
+        let received = ResultTransaction {
+            transaction_hash: "address_hash2".to_string(),
+            block_number: Some(200),
+            from: "0xea58fcA6849d79EAd1f26608855c2D6407d54Ce2".to_string(),
+            to: Some(address.clone()),
+            ..sent.clone()
+        };
+        let created = ResultTransaction {
+            transaction_hash: "address_hash3".to_string(),
+            block_number: Some(300),
+            from: "0xea58fcA6849d79EAd1f26608855c2D6407d54Ce2".to_string(),
+            to: None,
+            contract_address: Some(address.clone()),
+            ..sent.clone()
+        };
+
+        for trx in [&sent, &received, &created] {
+            save_transaction_to_db(&mut *db_trx, CHAIN_ID, trx)
+                .await
+                .expect("Failed to save trx to db");
+        }
+
+        let lowercase_address = address.to_lowercase();
+        let hashes = |transactions: Vec<ResultTransaction>| {
+            transactions
+                .into_iter()
+                .map(|trx| trx.transaction_hash)
+                .collect::<Vec<_>>()
+        };
+
+        let all =
+            fetch_address_transactions(&mut *db_trx, CHAIN_ID, &lowercase_address, None, None, 10)
+                .await
+                .expect("Failed to fetch address transactions");
+        assert_eq!(
+            hashes(all),
+            vec!["address_hash3", "address_hash2", "address_hash1"]
+        );
+
+        for (direction, expected) in [
+            (AddressDirection::Sent, "address_hash1"),
+            (AddressDirection::Received, "address_hash2"),
+            (AddressDirection::Created, "address_hash3"),
+        ] {
+            let transactions = fetch_address_transactions(
+                &mut *db_trx,
+                CHAIN_ID,
+                &lowercase_address,
+                Some(direction),
+                None,
+                10,
+            )
+            .await
+            .expect("Failed to fetch address transactions");
+            assert_eq!(hashes(transactions), vec![expected]);
+        }
+
+        // the next page starts after the cursor
+        let page = fetch_address_transactions(
+            &mut *db_trx,
+            CHAIN_ID,
+            &lowercase_address,
+            None,
+            Some((200, "address_hash2".to_string())),
+            1,
+        )
+        .await
+        .expect("Failed to fetch address transactions");
+        assert_eq!(page, vec![sent.clone().with_block_time()]);
+
+        // a page ending within a block continues with the next hash of the same block
+        let same_block = ResultTransaction {
+            transaction_hash: "address_hash2b".to_string(),
+            ..received.clone()
+        };
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &same_block)
+            .await
+            .expect("Failed to save trx to db");
+
+        let first_page =
+            fetch_address_transactions(&mut *db_trx, CHAIN_ID, &lowercase_address, None, None, 2)
+                .await
+                .expect("Failed to fetch address transactions");
+        assert_eq!(
+            hashes(first_page.clone()),
+            vec!["address_hash3", "address_hash2b"]
+        );
+
+        let cursor = decode_cursor(&encode_cursor(&first_page[1])).expect("Invalid cursor");
+        let second_page = fetch_address_transactions(
+            &mut *db_trx,
+            CHAIN_ID,
+            &lowercase_address,
+            None,
+            Some(cursor),
+            2,
+        )
+        .await
+        .expect("Failed to fetch address transactions");
+        assert_eq!(hashes(second_page), vec!["address_hash2", "address_hash1"]);
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+
+    #[tokio::test]
+    async fn test_same_hash_on_two_chains() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_trx_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create TRANSACTIONS table");
+
+        let sepolia_trx = ResultTransaction {
+            transaction_hash: "hash1".to_string(),
+            transaction_status: Some(1),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
+            from: "from_address".to_string(),
+            input: "0x".to_string(),
+            value: "0".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1700000000),
+            ..Default::default()
+        };
+        let mainnet_trx = ResultTransaction {
+            block_hash: Some("blockhash2".to_string()),
+            block_number: Some(200),
+            ..sepolia_trx.clone()
+        };
+
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &sepolia_trx)
+            .await
+            .expect("Failed to save trx to db");
+        save_transaction_to_db(&mut *db_trx, 1, &mainnet_trx)
+            .await
+            .expect("Failed to save trx to db");
+
+        let fetched_sepolia_trx =
+            check_transaction_in_db(&mut *db_trx, CHAIN_ID, &sepolia_trx.transaction_hash)
+                .await
+                .expect("Failed to fetch trx");
+        let fetched_mainnet_trx =
+            check_transaction_in_db(&mut *db_trx, 1, &mainnet_trx.transaction_hash)
+                .await
+                .expect("Failed to fetch trx");
+
+        assert_eq!(fetched_sepolia_trx, Some(sepolia_trx.with_block_time()));
+        assert_eq!(fetched_mainnet_trx, Some(mainnet_trx.with_block_time()));
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
 }
@@ -0,0 +1,292 @@
+use sqlx::{Executor, Postgres};
+
+use crate::utils::{
+    db::set_up::{chain_id_migration, transaction_fk_migration},
+    structs::transfer::{TokenTransfer, TokenTransferSummary},
+};
+
+pub async fn create_token_transfers_table<'c, E>(
+    executor: E,
+    default_chain_id: i64,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "token_transfers" table exists
+    let query = format!(
+        r#"
+        CREATE TABLE IF NOT EXISTS token_transfers (
+            chain_id BIGINT NOT NULL,
+            transaction_hash TEXT NOT NULL,
+            log_index INTEGER NOT NULL,
+            batch_index INTEGER NOT NULL,
+            standard TEXT NOT NULL,
+            token TEXT NOT NULL,
+            "from" TEXT NOT NULL,
+            "to" TEXT NOT NULL,
+            amount TEXT,
+            token_id TEXT,
+            PRIMARY KEY (chain_id, transaction_hash, log_index, batch_index),
+            FOREIGN KEY (chain_id, transaction_hash)
+                REFERENCES transactions (chain_id, transaction_hash) ON DELETE CASCADE
+        );
+        {}
+        {}
+        "#,
+        chain_id_migration(
+            "token_transfers",
+            Some("chain_id, transaction_hash, log_index, batch_index"),
+            default_chain_id
+        ),
+        transaction_fk_migration("token_transfers"),
+    );
+    executor.execute(query.as_str()).await?;
+
+    Ok(())
+}
+
+pub async fn save_token_transfer<'c, E>(
+    executor: E,
+    chain_id: i64,
+    transfer: &TokenTransfer,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO token_transfers (
+            transaction_hash,
+            log_index,
+            batch_index,
+            standard,
+            token,
+            "from",
+            "to",
+            amount,
+            token_id,
+            chain_id
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        ON CONFLICT (chain_id, transaction_hash, log_index, batch_index) DO NOTHING
+        "#,
+    )
+    .bind(&transfer.transaction_hash)
+    .bind(transfer.log_index)
+    .bind(transfer.batch_index)
+    .bind(&transfer.standard)
+    .bind(&transfer.token)
+    .bind(&transfer.from)
+    .bind(&transfer.to)
+    .bind(&transfer.amount)
+    .bind(&transfer.token_id)
+    .bind(chain_id)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_transaction_transfers<'c, E>(
+    executor: E,
+    chain_id: i64,
+    trx_hash: &String,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        DELETE FROM token_transfers
+        WHERE chain_id = $1 AND transaction_hash = $2
+        "#,
+    )
+    .bind(chain_id)
+    .bind(trx_hash)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn fetch_transaction_transfers<'c, E>(
+    executor: E,
+    chain_id: i64,
+    trx_hash: &String,
+) -> Result<Vec<TokenTransfer>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let transfers = sqlx::query_as::<_, TokenTransfer>(
+        r#"
+        SELECT
+            transaction_hash,
+            log_index,
+            batch_index,
+            standard,
+            token,
+            "from",
+            "to",
+            amount,
+            token_id
+        FROM token_transfers
+        WHERE chain_id = $1 AND transaction_hash = $2
+        ORDER BY log_index, batch_index
+        "#,
+    )
+    .bind(chain_id)
+    .bind(trx_hash)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(transfers)
+}
+
+// counts the transfers per token of each of the transactions
+pub async fn fetch_transfer_summaries<'c, E>(
+    executor: E,
+    chain_id: i64,
+    trx_hashes: Vec<String>,
+) -> Result<Vec<TokenTransferSummary>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let summaries = sqlx::query_as::<_, TokenTransferSummary>(
+        r#"
+        SELECT
+            transaction_hash,
+            token,
+            standard,
+            COUNT(*) AS transfers
+        FROM token_transfers
+        WHERE chain_id = $1 AND transaction_hash = ANY($2)
+        GROUP BY transaction_hash, token, standard
+        ORDER BY transaction_hash, MIN(log_index)
+        "#,
+    )
+    .bind(chain_id)
+    .bind(trx_hashes)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        load_config,
+        utils::{
+            db::transaction::{create_trx_table, save_transaction_to_db},
+            structs::transaction::{ResultTransaction, TransactionState},
+        },
+    };
+    use std::env;
+
+    use super::*;
+    use sqlx::Pool;
+
+    const CHAIN_ID: i64 = 11155111;
+
+    async fn fixture_pool() -> Pool<Postgres> {
+        load_config();
+
+        let database_url = env::var("DB_CONNECTION_URL").expect("DB_CONNECTION_URL must be set");
+        let pool = Pool::<Postgres>::connect(&database_url)
+            .await
+            .expect("Failed to connect to the database");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_save_and_fetch_token_transfers() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_trx_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create TRANSACTIONS table");
+        create_token_transfers_table(&mut *db_trx, CHAIN_ID)
+            .await
+            .expect("Failed to create TOKEN_TRANSFERS table");
+
+        let trx = ResultTransaction {
+            transaction_hash: "hash1".to_string(),
+            transaction_status: Some(1),
+            block_hash: Some("blockhash1".to_string()),
+            block_number: Some(100),
+            from: "from_address".to_string(),
+            to: Some("to_address".to_string()),
+            logs_count: 2,
+            input: "input_data".to_string(),
+            value: "0".to_string(),
+            state: TransactionState::Latest,
+            transaction_type: Some(2),
+            block_timestamp: Some(1700000000),
+            ..Default::default()
+        };
+
+        save_transaction_to_db(&mut *db_trx, CHAIN_ID, &trx)
+            .await
+            .expect("Failed to save trx to db");
+
+        let erc20_transfer = TokenTransfer {
+            transaction_hash: "hash1".to_string(),
+            log_index: 1,
+            batch_index: 0,
+            standard: "erc20".to_string(),
+            token: "token1".to_string(),
+            from: "from_address".to_string(),
+            to: "to_address".to_string(),
+            amount: Some("1000".to_string()),
+            token_id: None,
+        };
+        let erc721_transfer = TokenTransfer {
+            log_index: 0,
+            standard: "erc721".to_string(),
+            token: "token2".to_string(),
+            amount: None,
+            token_id: Some("7".to_string()),
+            ..erc20_transfer.clone()
+        };
+        let second_erc20_transfer = TokenTransfer {
+            log_index: 2,
+            ..erc20_transfer.clone()
+        };
+
+        for transfer in [&erc20_transfer, &erc721_transfer, &second_erc20_transfer] {
+            save_token_transfer(&mut *db_trx, CHAIN_ID, transfer)
+                .await
+                .expect("Failed to save token transfer");
+        }
+
+        let transfers = fetch_transaction_transfers(&mut *db_trx, CHAIN_ID, &trx.transaction_hash)
+            .await
+            .expect("Failed to fetch token transfers");
+        assert_eq!(
+            transfers,
+            vec![
+                erc721_transfer.clone(),
+                erc20_transfer.clone(),
+                second_erc20_transfer
+            ]
+        );
+
+        let summaries = fetch_transfer_summaries(&mut *db_trx, CHAIN_ID, vec!["hash1".to_string()])
+            .await
+            .expect("Failed to fetch transfer summaries");
+        assert_eq!(
+            summaries
+                .iter()
+                .map(|summary| (summary.token.as_str(), summary.transfers))
+                .collect::<Vec<_>>(),
+            vec![("token2", 1), ("token1", 2)]
+        );
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+}
@@ -0,0 +1,361 @@
+use sqlx::{Executor, Postgres};
+
+use crate::utils::structs::{reorg::CanonicalBlock, watchlist::WatchedAddress};
+
+pub async fn create_watchlist_table<'c, E>(executor: E) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "watchlist" table exists
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS watchlist (
+            chain_id BIGINT NOT NULL,
+            user_name TEXT NOT NULL,
+            address TEXT NOT NULL,
+            label TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (chain_id, user_name, address)
+        )
+        "#,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn create_indexer_cursors_table<'c, E>(executor: E) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "indexer_cursors" table exists, holding the last indexed block of each chain
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS indexer_cursors (
+            chain_id BIGINT PRIMARY KEY,
+            block_number INTEGER NOT NULL,
+            block_hash TEXT NOT NULL,
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+// watches the address for the user, updating the label when it is already watched
+pub async fn save_watched_address<'c, E>(
+    executor: E,
+    chain_id: i64,
+    user_name: &String,
+    address: &String,
+    label: Option<&String>,
+) -> Result<WatchedAddress, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let watched_address = sqlx::query_as::<_, WatchedAddress>(
+        r#"
+        INSERT INTO watchlist (chain_id, user_name, address, label)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (chain_id, user_name, address) DO UPDATE SET label = EXCLUDED.label
+        RETURNING address, label, EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at
+        "#,
+    )
+    .bind(chain_id)
+    .bind(user_name)
+    .bind(address)
+    .bind(label)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(watched_address)
+}
+
+pub async fn fetch_user_watchlist<'c, E>(
+    executor: E,
+    chain_id: i64,
+    user_name: &String,
+) -> Result<Vec<WatchedAddress>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let addresses = sqlx::query_as::<_, WatchedAddress>(
+        r#"
+        SELECT address, label, EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at
+        FROM watchlist
+        WHERE chain_id = $1 AND user_name = $2
+        ORDER BY created_at, address
+        "#,
+    )
+    .bind(chain_id)
+    .bind(user_name)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(addresses)
+}
+
+// returns false when the user did not watch the address
+pub async fn delete_watched_address<'c, E>(
+    executor: E,
+    chain_id: i64,
+    user_name: &String,
+    address: &String,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let result = sqlx::query(
+        r#"
+        DELETE FROM watchlist
+        WHERE chain_id = $1 AND user_name = $2 AND address = $3
+        "#,
+    )
+    .bind(chain_id)
+    .bind(user_name)
+    .bind(address)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// the addresses watched by any user of the chain
+pub async fn fetch_watched_addresses<'c, E>(
+    executor: E,
+    chain_id: i64,
+) -> Result<Vec<String>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let addresses: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT DISTINCT address
+        FROM watchlist
+        WHERE chain_id = $1
+        "#,
+    )
+    .bind(chain_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(addresses)
+}
+
+// the users watching any of the addresses, as (user name, address) pairs
+pub async fn fetch_address_watchers<'c, E>(
+    executor: E,
+    chain_id: i64,
+    addresses: &[String],
+) -> Result<Vec<(String, String)>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let watchers: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT user_name, address
+        FROM watchlist
+        WHERE chain_id = $1 AND address = ANY($2)
+        ORDER BY user_name, address
+        "#,
+    )
+    .bind(chain_id)
+    .bind(addresses)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(watchers)
+}
+
+pub async fn fetch_indexer_cursor<'c, E>(
+    executor: E,
+    chain_id: i64,
+) -> Result<Option<CanonicalBlock>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let cursor = sqlx::query_as::<_, CanonicalBlock>(
+        r#"
+        SELECT block_number, block_hash
+        FROM indexer_cursors
+        WHERE chain_id = $1
+        "#,
+    )
+    .bind(chain_id)
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(cursor)
+}
+
+// records the block as the last one indexed on the chain
+pub async fn save_indexer_cursor<'c, E>(
+    executor: E,
+    chain_id: i64,
+    block: &CanonicalBlock,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO indexer_cursors (chain_id, block_number, block_hash)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (chain_id) DO UPDATE SET
+            block_number = EXCLUDED.block_number,
+            block_hash = EXCLUDED.block_hash,
+            updated_at = NOW()
+        "#,
+    )
+    .bind(chain_id)
+    .bind(block.block_number)
+    .bind(&block.block_hash)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::load_config;
+    use std::env;
+
+    use super::*;
+    use sqlx::Pool;
+
+    const CHAIN_ID: i64 = 11155111;
+
+    async fn fixture_pool() -> Pool<Postgres> {
+        load_config();
+
+        let database_url = env::var("DB_CONNECTION_URL").expect("DB_CONNECTION_URL must be set");
+        let pool = Pool::<Postgres>::connect(&database_url)
+            .await
+            .expect("Failed to connect to the database");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_save_fetch_and_delete_watched_addresses() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_watchlist_table(&mut *db_trx)
+            .await
+            .expect("Failed to create WATCHLIST table");
+
+        let alice = "alice".to_string();
+        let bob = "bob".to_string();
+        let address = "0xabc".to_string();
+        let label = "cold wallet".to_string();
+
+        save_watched_address(&mut *db_trx, CHAIN_ID, &alice, &address, None)
+            .await
+            .expect("Failed to watch address");
+        let relabeled =
+            save_watched_address(&mut *db_trx, CHAIN_ID, &alice, &address, Some(&label))
+                .await
+                .expect("Failed to relabel address");
+        save_watched_address(&mut *db_trx, CHAIN_ID, &bob, &address, None)
+            .await
+            .expect("Failed to watch address");
+        save_watched_address(&mut *db_trx, 1, &bob, &"0xdef".to_string(), None)
+            .await
+            .expect("Failed to watch address");
+
+        assert_eq!(relabeled.label, Some(label));
+        assert_eq!(
+            fetch_user_watchlist(&mut *db_trx, CHAIN_ID, &alice)
+                .await
+                .expect("Failed to fetch watchlist"),
+            vec![relabeled]
+        );
+        // the address watched by both users is indexed once, other chains are not mixed in
+        assert_eq!(
+            fetch_watched_addresses(&mut *db_trx, CHAIN_ID)
+                .await
+                .expect("Failed to fetch watched addresses"),
+            vec![address.clone()]
+        );
+        assert_eq!(
+            fetch_address_watchers(
+                &mut *db_trx,
+                CHAIN_ID,
+                &[address.clone(), "0xdef".to_string()]
+            )
+            .await
+            .expect("Failed to fetch address watchers"),
+            vec![
+                (alice.clone(), address.clone()),
+                (bob.clone(), address.clone())
+            ]
+        );
+
+        assert!(
+            delete_watched_address(&mut *db_trx, CHAIN_ID, &alice, &address)
+                .await
+                .expect("Failed to delete watched address")
+        );
+        assert!(
+            !delete_watched_address(&mut *db_trx, CHAIN_ID, &alice, &address)
+                .await
+                .expect("Failed to delete watched address")
+        );
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+
+    #[tokio::test]
+    async fn test_save_and_fetch_indexer_cursor() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_indexer_cursors_table(&mut *db_trx)
+            .await
+            .expect("Failed to create INDEXER_CURSORS table");
+
+        assert_eq!(
+            fetch_indexer_cursor(&mut *db_trx, CHAIN_ID)
+                .await
+                .expect("Failed to fetch cursor"),
+            None
+        );
+
+        for block_number in [100, 101] {
+            save_indexer_cursor(
+                &mut *db_trx,
+                CHAIN_ID,
+                &CanonicalBlock {
+                    block_number,
+                    block_hash: format!("blockhash{}", block_number),
+                },
+            )
+            .await
+            .expect("Failed to save cursor");
+        }
+
+        assert_eq!(
+            fetch_indexer_cursor(&mut *db_trx, CHAIN_ID)
+                .await
+                .expect("Failed to fetch cursor"),
+            Some(CanonicalBlock {
+                block_number: 101,
+                block_hash: "blockhash101".to_string(),
+            })
+        );
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+}
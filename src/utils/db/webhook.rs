@@ -0,0 +1,628 @@
+use serde_json::Value;
+use sqlx::{types::Json, Executor, Postgres};
+
+use crate::utils::structs::webhook::{
+    DueDelivery, TrackedTransaction, Webhook, WebhookDelivery, WebhookEvent,
+};
+
+pub async fn create_webhooks_table<'c, E>(executor: E) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "webhooks" table exists
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS webhooks (
+            id SERIAL PRIMARY KEY,
+            chain_id BIGINT NOT NULL,
+            user_name TEXT NOT NULL,
+            url TEXT NOT NULL,
+            secret TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+        )
+        "#,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn create_tracked_transactions_table<'c, E>(executor: E) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "tracked_transactions" table exists
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS tracked_transactions (
+            chain_id BIGINT NOT NULL,
+            user_name TEXT NOT NULL,
+            transaction_hash TEXT NOT NULL,
+            confirmations INTEGER NOT NULL,
+            block_number INTEGER,
+            block_hash TEXT,
+            confirmed BOOLEAN NOT NULL DEFAULT FALSE,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            PRIMARY KEY (chain_id, user_name, transaction_hash)
+        )
+        "#,
+    )
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn create_webhook_deliveries_table<'c, E>(executor: E) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    // Ensure the "webhook_deliveries" table exists, the outbox of the webhook events.
+    // An event is enqueued once per webhook, its event_key tells repeated events apart
+    executor
+        .execute(
+            r#"
+        CREATE TABLE IF NOT EXISTS webhook_deliveries (
+            id BIGSERIAL PRIMARY KEY,
+            webhook_id INTEGER NOT NULL REFERENCES webhooks (id) ON DELETE CASCADE,
+            event TEXT NOT NULL,
+            event_key TEXT NOT NULL,
+            payload JSONB NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            attempts INTEGER NOT NULL DEFAULT 0,
+            last_status_code INTEGER,
+            last_error TEXT,
+            next_attempt_at TIMESTAMPTZ DEFAULT NOW(),
+            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+            delivered_at TIMESTAMPTZ,
+            UNIQUE (webhook_id, event_key)
+        );
+        CREATE INDEX IF NOT EXISTS webhook_deliveries_due_idx
+            ON webhook_deliveries (next_attempt_at) WHERE status = 'pending';
+        "#,
+        )
+        .await?;
+
+    Ok(())
+}
+
+pub async fn save_webhook<'c, E>(
+    executor: E,
+    chain_id: i64,
+    user_name: &String,
+    url: &String,
+    secret: &String,
+) -> Result<Webhook, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let webhook = sqlx::query_as::<_, Webhook>(
+        r#"
+        INSERT INTO webhooks (chain_id, user_name, url, secret)
+        VALUES ($1, $2, $3, $4)
+        RETURNING id, url, secret, EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at
+        "#,
+    )
+    .bind(chain_id)
+    .bind(user_name)
+    .bind(url)
+    .bind(secret)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(webhook)
+}
+
+pub async fn fetch_user_webhooks<'c, E>(
+    executor: E,
+    chain_id: i64,
+    user_name: &String,
+) -> Result<Vec<Webhook>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let webhooks = sqlx::query_as::<_, Webhook>(
+        r#"
+        SELECT id, url, secret, EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at
+        FROM webhooks
+        WHERE chain_id = $1 AND user_name = $2
+        ORDER BY id
+        "#,
+    )
+    .bind(chain_id)
+    .bind(user_name)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(webhooks)
+}
+
+// returns false when the webhook is not registered by the user,
+// its deliveries are removed by the cascading foreign key
+pub async fn delete_webhook<'c, E>(
+    executor: E,
+    chain_id: i64,
+    user_name: &String,
+    id: i32,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let result = sqlx::query(
+        r#"
+        DELETE FROM webhooks
+        WHERE chain_id = $1 AND user_name = $2 AND id = $3
+        "#,
+    )
+    .bind(chain_id)
+    .bind(user_name)
+    .bind(id)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// tracks the trx for the user, tracking it again sets its confirmations and checks it anew
+pub async fn save_tracked_transaction<'c, E>(
+    executor: E,
+    chain_id: i64,
+    user_name: &String,
+    transaction_hash: &String,
+    confirmations: i32,
+) -> Result<TrackedTransaction, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let tracked = sqlx::query_as::<_, TrackedTransaction>(
+        r#"
+        INSERT INTO tracked_transactions (chain_id, user_name, transaction_hash, confirmations)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (chain_id, user_name, transaction_hash) DO UPDATE SET
+            confirmations = EXCLUDED.confirmations,
+            confirmed = FALSE
+        RETURNING user_name, transaction_hash, confirmations, block_number, block_hash, confirmed,
+            EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at
+        "#,
+    )
+    .bind(chain_id)
+    .bind(user_name)
+    .bind(transaction_hash)
+    .bind(confirmations)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(tracked)
+}
+
+pub async fn fetch_user_tracked_transactions<'c, E>(
+    executor: E,
+    chain_id: i64,
+    user_name: &String,
+) -> Result<Vec<TrackedTransaction>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let tracked = sqlx::query_as::<_, TrackedTransaction>(
+        r#"
+        SELECT user_name, transaction_hash, confirmations, block_number, block_hash, confirmed,
+            EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at
+        FROM tracked_transactions
+        WHERE chain_id = $1 AND user_name = $2
+        ORDER BY created_at, transaction_hash
+        "#,
+    )
+    .bind(chain_id)
+    .bind(user_name)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(tracked)
+}
+
+// returns false when the user did not track the trx
+pub async fn delete_tracked_transaction<'c, E>(
+    executor: E,
+    chain_id: i64,
+    user_name: &String,
+    transaction_hash: &String,
+) -> Result<bool, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let result = sqlx::query(
+        r#"
+        DELETE FROM tracked_transactions
+        WHERE chain_id = $1 AND user_name = $2 AND transaction_hash = $3
+        "#,
+    )
+    .bind(chain_id)
+    .bind(user_name)
+    .bind(transaction_hash)
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// the tracked trxs of all users of the chain which did not reach their confirmations yet
+pub async fn fetch_unconfirmed_tracked_transactions<'c, E>(
+    executor: E,
+    chain_id: i64,
+) -> Result<Vec<TrackedTransaction>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let tracked = sqlx::query_as::<_, TrackedTransaction>(
+        r#"
+        SELECT user_name, transaction_hash, confirmations, block_number, block_hash, confirmed,
+            EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at
+        FROM tracked_transactions
+        WHERE chain_id = $1 AND NOT confirmed
+        "#,
+    )
+    .bind(chain_id)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(tracked)
+}
+
+// records the block the tracked trx was last seen in
+pub async fn update_tracked_transaction<'c, E>(
+    executor: E,
+    chain_id: i64,
+    tracked: &TrackedTransaction,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        UPDATE tracked_transactions
+        SET block_number = $4, block_hash = $5, confirmed = $6
+        WHERE chain_id = $1 AND user_name = $2 AND transaction_hash = $3
+        "#,
+    )
+    .bind(chain_id)
+    .bind(&tracked.user_name)
+    .bind(&tracked.transaction_hash)
+    .bind(tracked.block_number)
+    .bind(&tracked.block_hash)
+    .bind(tracked.confirmed)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+// adds the event to the outbox of every webhook of the user, an event already enqueued
+// with the same event_key is skipped. Returns the number of enqueued deliveries
+pub async fn enqueue_webhook_event<'c, E>(
+    executor: E,
+    chain_id: i64,
+    user_name: &String,
+    event: WebhookEvent,
+    event_key: &String,
+    payload: &Value,
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let result = sqlx::query(
+        r#"
+        INSERT INTO webhook_deliveries (webhook_id, event, event_key, payload)
+        SELECT id, $3, $4, $5
+        FROM webhooks
+        WHERE chain_id = $1 AND user_name = $2
+        ON CONFLICT (webhook_id, event_key) DO NOTHING
+        "#,
+    )
+    .bind(chain_id)
+    .bind(user_name)
+    .bind(event.as_str())
+    .bind(event_key)
+    .bind(Json(payload))
+    .execute(executor)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// the pending deliveries of all chains whose next attempt is due, oldest first
+pub async fn fetch_due_deliveries<'c, E>(
+    executor: E,
+    limit: i64,
+) -> Result<Vec<DueDelivery>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let deliveries = sqlx::query_as::<_, DueDelivery>(
+        r#"
+        SELECT d.id, w.chain_id, d.event, d.payload, d.attempts,
+            EXTRACT(EPOCH FROM d.created_at)::BIGINT AS created_at, w.url, w.secret
+        FROM webhook_deliveries d
+        JOIN webhooks w ON w.id = d.webhook_id
+        WHERE d.status = 'pending' AND d.next_attempt_at <= NOW()
+        ORDER BY d.next_attempt_at, d.id
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(deliveries)
+}
+
+// records an attempt of the delivery, a failed attempt is retried after retry_in_secs,
+// or given up on when it is None
+pub async fn record_delivery_attempt<'c, E>(
+    executor: E,
+    id: i64,
+    status_code: Option<i32>,
+    error: Option<&String>,
+    retry_in_secs: Option<i64>,
+) -> Result<(), sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        UPDATE webhook_deliveries
+        SET attempts = attempts + 1,
+            last_status_code = $2,
+            last_error = $3,
+            status = CASE
+                WHEN $3::TEXT IS NULL THEN 'delivered'
+                WHEN $4::BIGINT IS NULL THEN 'failed'
+                ELSE 'pending'
+            END,
+            next_attempt_at = CASE
+                WHEN $3::TEXT IS NOT NULL AND $4::BIGINT IS NOT NULL
+                THEN NOW() + make_interval(secs => $4::BIGINT)
+            END,
+            delivered_at = CASE WHEN $3::TEXT IS NULL THEN NOW() END
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(status_code)
+    .bind(error)
+    .bind(retry_in_secs)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+// the most recent deliveries to the webhooks of the user
+pub async fn fetch_user_webhook_deliveries<'c, E>(
+    executor: E,
+    chain_id: i64,
+    user_name: &String,
+    limit: i64,
+) -> Result<Vec<WebhookDelivery>, sqlx::Error>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+        r#"
+        SELECT d.id, d.webhook_id, d.event, d.payload, d.status, d.attempts, d.last_status_code,
+            d.last_error,
+            EXTRACT(EPOCH FROM d.next_attempt_at)::BIGINT AS next_attempt_at,
+            EXTRACT(EPOCH FROM d.created_at)::BIGINT AS created_at,
+            EXTRACT(EPOCH FROM d.delivered_at)::BIGINT AS delivered_at
+        FROM webhook_deliveries d
+        JOIN webhooks w ON w.id = d.webhook_id
+        WHERE w.chain_id = $1 AND w.user_name = $2
+        ORDER BY d.id DESC
+        LIMIT $3
+        "#,
+    )
+    .bind(chain_id)
+    .bind(user_name)
+    .bind(limit)
+    .fetch_all(executor)
+    .await?;
+
+    Ok(deliveries)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::load_config;
+    use std::env;
+
+    use super::*;
+    use serde_json::json;
+    use sqlx::Pool;
+
+    const CHAIN_ID: i64 = 11155111;
+
+    async fn fixture_pool() -> Pool<Postgres> {
+        load_config();
+
+        let database_url = env::var("DB_CONNECTION_URL").expect("DB_CONNECTION_URL must be set");
+        let pool = Pool::<Postgres>::connect(&database_url)
+            .await
+            .expect("Failed to connect to the database");
+
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_enqueue_and_deliver_webhook_events() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_webhooks_table(&mut *db_trx)
+            .await
+            .expect("Failed to create WEBHOOKS table");
+        create_webhook_deliveries_table(&mut *db_trx)
+            .await
+            .expect("Failed to create WEBHOOK_DELIVERIES table");
+
+        let user_name = "alice".to_string();
+        let webhook = save_webhook(
+            &mut *db_trx,
+            CHAIN_ID,
+            &user_name,
+            &"http://localhost/hook".to_string(),
+            &"secret".to_string(),
+        )
+        .await
+        .expect("Failed to save webhook");
+        // webhooks of other chains do not receive the event
+        save_webhook(
+            &mut *db_trx,
+            1,
+            &user_name,
+            &"http://localhost/mainnet".to_string(),
+            &"secret".to_string(),
+        )
+        .await
+        .expect("Failed to save webhook");
+
+        let event_key = "transaction.mined:0x01:0x02".to_string();
+        let payload = json!({ "transactionHash": "0x01" });
+        for expected in [1, 0] {
+            let enqueued = enqueue_webhook_event(
+                &mut *db_trx,
+                CHAIN_ID,
+                &user_name,
+                WebhookEvent::TransactionMined,
+                &event_key,
+                &payload,
+            )
+            .await
+            .expect("Failed to enqueue event");
+            // the same event is only enqueued once
+            assert_eq!(enqueued, expected);
+        }
+
+        let due: Vec<DueDelivery> = fetch_due_deliveries(&mut *db_trx, 1000)
+            .await
+            .expect("Failed to fetch due deliveries")
+            .into_iter()
+            .filter(|delivery| delivery.url == webhook.url)
+            .collect();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].chain_id, CHAIN_ID);
+        assert_eq!(due[0].payload.0, payload);
+
+        // a failed attempt is retried later, so it is no longer due
+        record_delivery_attempt(
+            &mut *db_trx,
+            due[0].id,
+            Some(500),
+            Some(&"Internal Server Error".to_string()),
+            Some(60),
+        )
+        .await
+        .expect("Failed to record attempt");
+        assert!(fetch_due_deliveries(&mut *db_trx, 1000)
+            .await
+            .expect("Failed to fetch due deliveries")
+            .iter()
+            .all(|delivery| delivery.id != due[0].id));
+
+        let deliveries = fetch_user_webhook_deliveries(&mut *db_trx, CHAIN_ID, &user_name, 20)
+            .await
+            .expect("Failed to fetch deliveries");
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, "pending");
+        assert_eq!(deliveries[0].attempts, 1);
+        assert_eq!(deliveries[0].last_status_code, Some(500));
+        assert!(deliveries[0].next_attempt_at.is_some());
+
+        record_delivery_attempt(&mut *db_trx, due[0].id, Some(200), None, None)
+            .await
+            .expect("Failed to record attempt");
+        let deliveries = fetch_user_webhook_deliveries(&mut *db_trx, CHAIN_ID, &user_name, 20)
+            .await
+            .expect("Failed to fetch deliveries");
+        assert_eq!(deliveries[0].status, "delivered");
+        assert_eq!(deliveries[0].attempts, 2);
+        assert_eq!(deliveries[0].next_attempt_at, None);
+        assert!(deliveries[0].delivered_at.is_some());
+
+        // deleting the webhook removes its deliveries
+        assert!(
+            delete_webhook(&mut *db_trx, CHAIN_ID, &user_name, webhook.id)
+                .await
+                .expect("Failed to delete webhook")
+        );
+        assert!(
+            fetch_user_webhook_deliveries(&mut *db_trx, CHAIN_ID, &user_name, 20)
+                .await
+                .expect("Failed to fetch deliveries")
+                .is_empty()
+        );
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+
+    #[tokio::test]
+    async fn test_save_and_update_tracked_transactions() {
+        let pool = fixture_pool().await;
+        let mut db_trx = pool.begin().await.expect("Pool transaction failed");
+
+        create_tracked_transactions_table(&mut *db_trx)
+            .await
+            .expect("Failed to create TRACKED_TRANSACTIONS table");
+
+        let user_name = "alice".to_string();
+        let transaction_hash = "0xtracked".to_string();
+        let mut tracked =
+            save_tracked_transaction(&mut *db_trx, CHAIN_ID, &user_name, &transaction_hash, 12)
+                .await
+                .expect("Failed to track trx");
+        assert_eq!(tracked.block_number, None);
+        assert!(!tracked.confirmed);
+
+        tracked.block_number = Some(100);
+        tracked.block_hash = Some("blockhash100".to_string());
+        tracked.confirmed = true;
+        update_tracked_transaction(&mut *db_trx, CHAIN_ID, &tracked)
+            .await
+            .expect("Failed to update tracked trx");
+
+        assert_eq!(
+            fetch_user_tracked_transactions(&mut *db_trx, CHAIN_ID, &user_name)
+                .await
+                .expect("Failed to fetch tracked trxs"),
+            vec![tracked.clone()]
+        );
+        assert!(
+            fetch_unconfirmed_tracked_transactions(&mut *db_trx, CHAIN_ID)
+                .await
+                .expect("Failed to fetch unconfirmed trxs")
+                .iter()
+                .all(|unconfirmed| unconfirmed.transaction_hash != transaction_hash)
+        );
+
+        // tracking it again checks it anew
+        let retracked =
+            save_tracked_transaction(&mut *db_trx, CHAIN_ID, &user_name, &transaction_hash, 20)
+                .await
+                .expect("Failed to track trx");
+        assert_eq!(retracked.confirmations, 20);
+        assert!(!retracked.confirmed);
+
+        assert!(
+            delete_tracked_transaction(&mut *db_trx, CHAIN_ID, &user_name, &transaction_hash)
+                .await
+                .expect("Failed to delete tracked trx")
+        );
+
+        db_trx
+            .rollback()
+            .await
+            .expect("Failed to rollback test trx");
+    }
+}
@@ -0,0 +1,353 @@
+use std::{str::FromStr, time::Duration};
+
+use alloy::{primitives::Address, providers::Provider};
+use axum::response::sse::Event;
+use tokio::sync::mpsc::Sender;
+
+use crate::config::{AppState, TRANSACTION_EVENTS_INTERVAL_SECS};
+
+use super::{
+    structs::{
+        chain::ChainError,
+        events::{TransactionEvent, TransactionEventKind, TransactionStatus},
+        transaction::{ResultTransaction, TransactionState},
+    },
+    transaction::{request_deadline, resolve_transactions},
+    webhook::confirmations,
+};
+
+// the consecutive polls a trx must be unknown to the node on before it is dropped or replaced,
+// a node behind a load balancer might briefly miss a trx another node already returned
+const MISSED_POLLS_BEFORE_DROPPED: u32 = 3;
+
+// turns the successive statuses of a trx into the events of its stream
+#[derive(Debug)]
+pub struct StatusTracker {
+    transaction_hash: String,
+    confirmations: u64, // the confirmations the confirmations event is sent at
+    previous: Option<TransactionStatus>,
+    confirmed: bool,
+    missed_polls: u32, // the consecutive polls the node did not know the trx
+}
+impl StatusTracker {
+    pub fn new(transaction_hash: String, confirmations: u64) -> StatusTracker {
+        StatusTracker {
+            transaction_hash,
+            confirmations,
+            previous: None,
+            confirmed: false,
+            missed_polls: 0,
+        }
+    }
+
+    fn event(
+        &self,
+        kind: TransactionEventKind,
+        block: Option<&(i32, String)>,
+        confirmations: Option<u64>,
+    ) -> TransactionEvent {
+        TransactionEvent {
+            kind,
+            transaction_hash: self.transaction_hash.clone(),
+            block_number: block.map(|(block_number, _)| *block_number),
+            block_hash: block.map(|(_, block_hash)| block_hash.clone()),
+            confirmations,
+        }
+    }
+
+    pub fn observe(&mut self, status: TransactionStatus) -> Vec<TransactionEvent> {
+        // a trx missed on fewer polls keeps its previous status
+        if matches!(
+            status,
+            TransactionStatus::Dropped | TransactionStatus::Replaced
+        ) {
+            self.missed_polls += 1;
+            if self.missed_polls < MISSED_POLLS_BEFORE_DROPPED {
+                return vec![];
+            }
+        } else {
+            self.missed_polls = 0;
+        }
+
+        let included_block = |status: &TransactionStatus| match status {
+            TransactionStatus::Included {
+                block_number,
+                block_hash,
+                ..
+            } => Some((*block_number, block_hash.clone())),
+            _ => None,
+        };
+        let previous_block = self.previous.as_ref().and_then(included_block);
+        let block = included_block(&status);
+        let mut events = vec![];
+
+        // the trx left its block, it is pending, dropped or included in another block now
+        if previous_block.is_some() && previous_block != block {
+            events.push(self.event(TransactionEventKind::Reorged, previous_block.as_ref(), None));
+            self.confirmed = false;
+        }
+
+        match &status {
+            TransactionStatus::Pending => {
+                if self.previous != Some(TransactionStatus::Pending) {
+                    events.push(self.event(TransactionEventKind::Pending, None, None));
+                }
+            }
+            TransactionStatus::Included {
+                confirmations,
+                finalized,
+                ..
+            } => {
+                if previous_block != block {
+                    events.push(self.event(
+                        TransactionEventKind::Included,
+                        block.as_ref(),
+                        Some(*confirmations),
+                    ));
+                }
+                if !self.confirmed && *confirmations >= self.confirmations {
+                    self.confirmed = true;
+                    events.push(self.event(
+                        TransactionEventKind::Confirmations,
+                        block.as_ref(),
+                        Some(*confirmations),
+                    ));
+                }
+                if *finalized {
+                    events.push(self.event(
+                        TransactionEventKind::Finalized,
+                        block.as_ref(),
+                        Some(*confirmations),
+                    ));
+                }
+            }
+            TransactionStatus::Dropped => {
+                events.push(self.event(TransactionEventKind::Dropped, None, None))
+            }
+            TransactionStatus::Replaced => {
+                events.push(self.event(TransactionEventKind::Replaced, None, None))
+            }
+        }
+
+        self.previous = Some(status);
+        events
+    }
+}
+
+pub fn to_sse_event(event: &TransactionEvent) -> Event {
+    Event::default()
+        .event(event.kind.as_str())
+        .json_data(event)
+        .unwrap_or_default()
+}
+
+// a trx the node no longer knows was replaced when the sender's nonce moved past it
+async fn is_replaced(state: &AppState, trx: &ResultTransaction) -> Option<bool> {
+    let Some(nonce) = trx.nonce else {
+        return Some(false);
+    };
+    let from = Address::from_str(&trx.from).ok()?;
+    let sender_nonce = state.eth_client.get_transaction_count(from).await.ok()?;
+
+    Some(sender_nonce > nonce as u64)
+}
+
+// the status of the resolved trx, None when it could not be resolved this time
+async fn transaction_status(
+    state: &AppState,
+    resolved: &Result<ResultTransaction, ChainError>,
+    last_seen: &mut ResultTransaction,
+) -> Option<TransactionStatus> {
+    match resolved {
+        Ok(trx) => {
+            *last_seen = trx.clone();
+
+            match trx.block_number.zip(trx.block_hash.clone()) {
+                None => Some(TransactionStatus::Pending),
+                Some((block_number, block_hash)) => {
+                    let head = state.eth_client.get_block_number().await.ok()?;
+                    Some(TransactionStatus::Included {
+                        block_number,
+                        block_hash,
+                        confirmations: confirmations(block_number, head),
+                        finalized: trx.state == TransactionState::Finalized,
+                    })
+                }
+            }
+        }
+        Err(ChainError::NotFound(_)) => match is_replaced(state, last_seen).await? {
+            true => Some(TransactionStatus::Replaced),
+            false => Some(TransactionStatus::Dropped),
+        },
+        Err(_) => None,
+    }
+}
+
+// resolves the trx every TRANSACTION_EVENTS_INTERVAL_SECS through the db cache and the node,
+// sending its events until a final one is sent or the client disconnects
+pub async fn stream_transaction_events(
+    state: AppState,
+    trx: ResultTransaction,
+    confirmations: u64,
+    sender: Sender<TransactionEvent>,
+) {
+    let transaction_hash = trx.transaction_hash.clone();
+    let mut tracker = StatusTracker::new(transaction_hash.clone(), confirmations);
+    let mut last_seen = trx.clone();
+    let mut resolved = Ok(trx);
+
+    loop {
+        if let Some(status) = transaction_status(&state, &resolved, &mut last_seen).await {
+            for event in tracker.observe(status) {
+                let is_final = event.kind.is_final();
+                if sender.send(event).await.is_err() || is_final {
+                    return;
+                }
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(*TRANSACTION_EVENTS_INTERVAL_SECS)).await;
+        if sender.is_closed() {
+            return;
+        }
+
+        resolved = resolve_transactions(&state, vec![transaction_hash.clone()], request_deadline())
+            .await
+            .pop()
+            .map(|(_, trx)| trx)
+            .unwrap_or(Err(ChainError::DeadlineExceeded));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn included(block_number: i32, block_hash: &str, confirmations: u64) -> TransactionStatus {
+        TransactionStatus::Included {
+            block_number,
+            block_hash: block_hash.to_string(),
+            confirmations,
+            finalized: false,
+        }
+    }
+
+    fn kinds(events: Vec<TransactionEvent>) -> Vec<TransactionEventKind> {
+        events.into_iter().map(|event| event.kind).collect()
+    }
+
+    #[test]
+    fn test_pending_to_finalized() {
+        let mut tracker = StatusTracker::new("0x01".to_string(), 3);
+
+        assert_eq!(
+            kinds(tracker.observe(TransactionStatus::Pending)),
+            vec![TransactionEventKind::Pending]
+        );
+        assert!(tracker.observe(TransactionStatus::Pending).is_empty());
+
+        let events = tracker.observe(included(100, "0xa", 1));
+        assert_eq!(events[0].kind, TransactionEventKind::Included);
+        assert_eq!(events[0].block_number, Some(100));
+        assert_eq!(events[0].confirmations, Some(1));
+        assert!(tracker.observe(included(100, "0xa", 2)).is_empty());
+
+        assert_eq!(
+            kinds(tracker.observe(included(100, "0xa", 3))),
+            vec![TransactionEventKind::Confirmations]
+        );
+        assert!(tracker.observe(included(100, "0xa", 4)).is_empty());
+
+        let events = tracker.observe(TransactionStatus::Included {
+            block_number: 100,
+            block_hash: "0xa".to_string(),
+            confirmations: 70,
+            finalized: true,
+        });
+        assert_eq!(kinds(events.clone()), vec![TransactionEventKind::Finalized]);
+        assert!(events[0].kind.is_final());
+    }
+
+    #[test]
+    fn test_already_finalized_trx() {
+        let mut tracker = StatusTracker::new("0x01".to_string(), 3);
+
+        assert_eq!(
+            kinds(tracker.observe(TransactionStatus::Included {
+                block_number: 100,
+                block_hash: "0xa".to_string(),
+                confirmations: 70,
+                finalized: true,
+            })),
+            vec![
+                TransactionEventKind::Included,
+                TransactionEventKind::Confirmations,
+                TransactionEventKind::Finalized
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reorged_dropped_and_replaced() {
+        let mut tracker = StatusTracker::new("0x01".to_string(), 2);
+        tracker.observe(included(100, "0xa", 2));
+
+        // included in another block, the confirmations are counted again
+        let events = tracker.observe(included(101, "0xb", 1));
+        assert_eq!(
+            kinds(events.clone()),
+            vec![
+                TransactionEventKind::Reorged,
+                TransactionEventKind::Included
+            ]
+        );
+        assert_eq!(events[0].block_hash, Some("0xa".to_string()));
+        assert_eq!(
+            kinds(tracker.observe(included(101, "0xb", 2))),
+            vec![TransactionEventKind::Confirmations]
+        );
+
+        for _ in 1..MISSED_POLLS_BEFORE_DROPPED {
+            assert!(tracker.observe(TransactionStatus::Dropped).is_empty());
+        }
+        assert_eq!(
+            kinds(tracker.observe(TransactionStatus::Dropped)),
+            vec![TransactionEventKind::Reorged, TransactionEventKind::Dropped]
+        );
+
+        let mut tracker = StatusTracker::new("0x01".to_string(), 2);
+        tracker.observe(TransactionStatus::Pending);
+        for _ in 1..MISSED_POLLS_BEFORE_DROPPED {
+            assert!(tracker.observe(TransactionStatus::Replaced).is_empty());
+        }
+        let events = tracker.observe(TransactionStatus::Replaced);
+        assert_eq!(kinds(events.clone()), vec![TransactionEventKind::Replaced]);
+        assert!(events[0].kind.is_final());
+    }
+
+    #[test]
+    fn test_transient_not_found() {
+        let mut tracker = StatusTracker::new("0x01".to_string(), 3);
+        tracker.observe(TransactionStatus::Pending);
+
+        // the node missed the pending trx once, then returned it included
+        assert!(tracker.observe(TransactionStatus::Dropped).is_empty());
+        assert_eq!(
+            kinds(tracker.observe(included(100, "0xa", 1))),
+            vec![TransactionEventKind::Included]
+        );
+
+        // an included trx missed once keeps its block
+        assert!(tracker.observe(TransactionStatus::Dropped).is_empty());
+        assert!(tracker.observe(included(100, "0xa", 2)).is_empty());
+
+        // the missed polls are counted again after the trx was seen
+        for _ in 1..MISSED_POLLS_BEFORE_DROPPED {
+            assert!(tracker.observe(TransactionStatus::Dropped).is_empty());
+        }
+        assert_eq!(
+            kinds(tracker.observe(included(100, "0xa", 3))),
+            vec![TransactionEventKind::Confirmations]
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use std::{collections::HashSet, time::Duration};
+
+use alloy::{
+    consensus::Transaction, eips::BlockNumberOrTag, primitives::Address, providers::Provider,
+    rpc::types::BlockTransactionsKind,
+};
+use futures::StreamExt;
+
+use crate::config::{
+    AppState, INDEXER_MAX_CATCH_UP_BLOCKS, INDEXER_POLL_INTERVAL_SECS, REORG_CHECK_DEPTH,
+    RPC_BATCH_SIZE,
+};
+
+use super::{
+    chain::fetch_batch_from_chain,
+    db::watchlist::{fetch_indexer_cursor, fetch_watched_addresses, save_indexer_cursor},
+    structs::reorg::CanonicalBlock,
+    webhook::notify_address_watchers,
+};
+
+// the address of the contract created by a trx without recipient, derived from the sender
+// and its nonce like the contractAddress of the receipt
+pub fn created_contract_address(from: Address, to: Option<Address>, nonce: u64) -> Option<Address> {
+    to.is_none().then(|| from.create(nonce))
+}
+
+// whether a trx is sent from or to, or creates one of the lowercase watched addresses
+pub fn touches_watchlist(
+    from: Address,
+    to: Option<Address>,
+    contract_address: Option<Address>,
+    watched: &HashSet<String>,
+) -> bool {
+    std::iter::once(from)
+        .chain(to)
+        .chain(contract_address)
+        .any(|address| watched.contains(&address.to_string().to_lowercase()))
+}
+
+// saves the trxs of the block touching a watched address, returns the scanned block
+async fn index_block(
+    state: &AppState,
+    block_number: u64,
+    watched: &HashSet<String>,
+) -> Result<(CanonicalBlock, String), String> {
+    // the trxs are only needed when an address is watched
+    let kind = if watched.is_empty() {
+        BlockTransactionsKind::Hashes
+    } else {
+        BlockTransactionsKind::Full
+    };
+    let block = state
+        .eth_client
+        .get_block_by_number(BlockNumberOrTag::Number(block_number), kind)
+        .await
+        .map_err(|e| format!("Failed to fetch block {}: {}", block_number, e))?
+        .ok_or(format!("Block {} not found", block_number))?;
+
+    let matched: Vec<String> = block
+        .transactions
+        .txns()
+        .filter(|trx| {
+            let contract_address =
+                created_contract_address(trx.from, trx.inner.to(), trx.inner.nonce());
+            touches_watchlist(trx.from, trx.inner.to(), contract_address, watched)
+        })
+        .map(|trx| trx.inner.tx_hash().to_string())
+        .collect();
+
+    // the matched trxs are saved like any fetched trx, a failed fetch scans the block again
+    for chunk in matched.chunks(*RPC_BATCH_SIZE) {
+        for fetched in fetch_batch_from_chain(chunk, state).await {
+            let trx = fetched
+                .map_err(|e| format!("Failed to fetch trx of block {}: {}", block_number, e))?;
+            notify_address_watchers(state, &trx).await?;
+        }
+    }
+    if !matched.is_empty() {
+        println!(
+            "Indexed {} watched trxs of block {} on {}",
+            matched.len(),
+            block_number,
+            state.chain.name
+        );
+    }
+
+    Ok((
+        CanonicalBlock {
+            block_number: block_number as i32,
+            block_hash: block.header.hash.to_string(),
+        },
+        block.header.parent_hash.to_string(),
+    ))
+}
+
+// scans the blocks after the stored cursor up to the head, moving the cursor after each block
+pub async fn index_new_blocks(state: &AppState, head: u64) -> Result<(), String> {
+    let watched: HashSet<String> = fetch_watched_addresses(&state.db_connection, state.chain_id())
+        .await
+        .map_err(|e| format!("Failed to fetch watched addresses: {}", e))?
+        .into_iter()
+        .collect();
+    let mut cursor = fetch_indexer_cursor(&state.db_connection, state.chain_id())
+        .await
+        .map_err(|e| format!("Failed to fetch indexer cursor: {}", e))?;
+
+    // without a cursor the indexer starts at the head, a long downtime is not caught up on
+    let oldest = head.saturating_sub(INDEXER_MAX_CATCH_UP_BLOCKS.saturating_sub(1));
+    let mut block_number = match &cursor {
+        Some(cursor) => (cursor.block_number as u64 + 1).max(oldest),
+        None => head,
+    };
+
+    while block_number <= head {
+        let (block, parent_hash) = index_block(state, block_number, &watched).await?;
+
+        // the cursor block was reorged, the blocks within REORG_CHECK_DEPTH are scanned again
+        if let Some(previous) = cursor.as_ref().filter(|previous| {
+            previous.block_number as u64 + 1 == block_number && previous.block_hash != parent_hash
+        }) {
+            println!(
+                "Indexer cursor block {} on {} was reorged, rescanning",
+                previous.block_hash, state.chain.name
+            );
+            cursor = None;
+            block_number = block_number.saturating_sub(*REORG_CHECK_DEPTH).max(oldest);
+            continue;
+        }
+
+        save_indexer_cursor(&state.db_connection, state.chain_id(), &block)
+            .await
+            .map_err(|e| format!("Failed to save indexer cursor: {}", e))?;
+        cursor = Some(block);
+        block_number += 1;
+    }
+
+    Ok(())
+}
+
+async fn index_head(state: &AppState, head: u64) {
+    if let Err(err_msg) = index_new_blocks(state, head).await {
+        println!("Indexing failed on {}, error:{}", state.chain.name, err_msg);
+    }
+}
+
+// background task indexing every new block, new heads are subscribed to over a ws or ipc
+// endpoint and polled every INDEXER_POLL_INTERVAL_SECS when only http endpoints are configured
+pub async fn run_indexer(state: AppState) {
+    loop {
+        if let Some(provider) = state.rpc_pool.subscription_provider() {
+            match provider.subscribe_blocks().await {
+                Ok(subscription) => {
+                    let mut heads = subscription.into_stream();
+                    while let Some(header) = heads.next().await {
+                        index_head(&state, header.number).await;
+                    }
+                    println!(
+                        "New heads subscription ended on {}, resubscribing",
+                        state.chain.name
+                    );
+                }
+                Err(e) => println!(
+                    "Failed to subscribe to new heads on {}, polling instead: {}",
+                    state.chain.name, e
+                ),
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(*INDEXER_POLL_INTERVAL_SECS)).await;
+        match state.eth_client.get_block_number().await {
+            Ok(head) => index_head(&state, head).await,
+            Err(e) => println!(
+                "Failed to fetch latest block number on {}: {}",
+                state.chain.name, e
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::primitives::address;
+
+    #[test]
+    fn test_touches_watchlist() {
+        let watched_address = address!("d8dA6BF26964aF9D7eEd9e03E53415D37aA96045");
+        let other_address = address!("0000000000000000000000000000000000000001");
+        let watched: HashSet<String> =
+            HashSet::from(["0xd8da6bf26964af9d7eed9e03e53415d37aa96045".to_string()]);
+
+        assert!(touches_watchlist(
+            watched_address,
+            Some(other_address),
+            None,
+            &watched
+        ));
+        assert!(touches_watchlist(
+            other_address,
+            Some(watched_address),
+            None,
+            &watched
+        ));
+        // contract creations have no recipient
+        assert!(touches_watchlist(watched_address, None, None, &watched));
+        assert!(!touches_watchlist(other_address, None, None, &watched));
+        assert!(touches_watchlist(
+            other_address,
+            None,
+            Some(watched_address),
+            &watched
+        ));
+        assert!(!touches_watchlist(
+            other_address,
+            Some(other_address),
+            None,
+            &HashSet::new()
+        ));
+    }
+
+    #[test]
+    fn test_created_contract_address() {
+        let from = address!("b20a608c624Ca5003905aA834De7156C68b2E1d0");
+
+        // the beacon deposit contract
+        assert_eq!(
+            created_contract_address(from, None, 0),
+            Some(address!("00000000219ab540356cBB839Cbe05303d7705Fa"))
+        );
+        assert_eq!(created_contract_address(from, Some(Address::ZERO), 0), None);
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt::Write;
+
+use super::structs::circuit_breaker::CircuitBreakerStatus;
+
+// the name, type, help text and value of a metric
+type Metric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&CircuitBreakerStatus) -> u64,
+);
+
+// renders the circuit breaker of every chain in the prometheus text format
+pub fn render_metrics(breakers: &[(String, CircuitBreakerStatus)]) -> String {
+    let metrics: [Metric; 4] = [
+        (
+            "eth_fetcher_circuit_breaker_state",
+            "gauge",
+            "State of the circuit breaker around the node, 0 closed, 1 open, 2 half-open",
+            |status| status.state.as_metric() as u64,
+        ),
+        (
+            "eth_fetcher_circuit_breaker_consecutive_failures",
+            "gauge",
+            "Consecutive failed node calls",
+            |status| status.consecutive_failures as u64,
+        ),
+        (
+            "eth_fetcher_circuit_breaker_opened_total",
+            "counter",
+            "Times the circuit breaker opened",
+            |status| status.times_opened,
+        ),
+        (
+            "eth_fetcher_circuit_breaker_refused_calls_total",
+            "counter",
+            "Node calls refused while the circuit breaker was open",
+            |status| status.refused_calls,
+        ),
+    ];
+
+    let mut output = String::new();
+    for (name, kind, help, value) in metrics {
+        let _ = writeln!(output, "# HELP {} {}", name, help);
+        let _ = writeln!(output, "# TYPE {} {}", name, kind);
+        for (chain, status) in breakers {
+            let _ = writeln!(output, "{}{{chain=\"{}\"}} {}", name, chain, value(status));
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::structs::circuit_breaker::CircuitState;
+
+    #[test]
+    fn test_render_metrics() {
+        let status = CircuitBreakerStatus {
+            state: CircuitState::Open,
+            consecutive_failures: 5,
+            times_opened: 1,
+            refused_calls: 3,
+        };
+
+        let output = render_metrics(&[("sepolia".to_string(), status)]);
+
+        assert!(output.contains("# TYPE eth_fetcher_circuit_breaker_state gauge\n"));
+        assert!(output.contains("eth_fetcher_circuit_breaker_state{chain=\"sepolia\"} 1\n"));
+        assert!(output
+            .contains("eth_fetcher_circuit_breaker_refused_calls_total{chain=\"sepolia\"} 3\n"));
+    }
+}
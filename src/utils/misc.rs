@@ -1,6 +1,9 @@
 use alloy::primitives::B256;
 use hex::FromHex;
 use serde::Deserialize;
+use time::{
+    format_description::well_known::Rfc3339, macros::format_description, Date, OffsetDateTime,
+};
 
 pub fn parse_b256_from_str(hash_str: &str) -> Result<B256, String> {
     // remove hex prefix
@@ -23,3 +26,58 @@ where
     let s = String::deserialize(deserializer)?;
     Ok(s.split(',').map(|item| item.trim().to_string()).collect())
 }
+
+// formats a unix timestamp (seconds) as ISO-8601 in UTC, e.g. 2024-01-01T00:00:00Z
+pub fn unix_to_iso8601(timestamp: i64) -> Option<String> {
+    OffsetDateTime::from_unix_timestamp(timestamp)
+        .ok()?
+        .format(&Rfc3339)
+        .ok()
+}
+
+// parses unix seconds, an ISO-8601 date time or a date (YYYY-MM-DD) to unix seconds,
+// a date is the start of the day, or the end of the day when end_of_day is true
+pub fn parse_date_to_unix(date: &str, end_of_day: bool) -> Result<i64, String> {
+    if let Ok(timestamp) = date.parse::<i64>() {
+        return Ok(timestamp);
+    }
+
+    if let Ok(date_time) = OffsetDateTime::parse(date, &Rfc3339) {
+        return Ok(date_time.unix_timestamp());
+    }
+
+    let day = Date::parse(date, format_description!("[year]-[month]-[day]"))
+        .map_err(|_| format!("Invalid date {}", date))?;
+    let start_of_day = day.midnight().assume_utc().unix_timestamp();
+
+    Ok(if end_of_day {
+        start_of_day + 86399
+    } else {
+        start_of_day
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unix_to_iso8601() {
+        assert_eq!(
+            unix_to_iso8601(1700000000),
+            Some("2023-11-14T22:13:20Z".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_date_to_unix() {
+        assert_eq!(parse_date_to_unix("1700000000", false), Ok(1700000000));
+        assert_eq!(
+            parse_date_to_unix("2023-11-14T22:13:20Z", false),
+            Ok(1700000000)
+        );
+        assert_eq!(parse_date_to_unix("2023-11-14", false), Ok(1699920000));
+        assert_eq!(parse_date_to_unix("2023-11-14", true), Ok(1700006399));
+        assert!(parse_date_to_unix("14.11.2023", false).is_err());
+    }
+}
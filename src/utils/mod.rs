@@ -1,6 +1,21 @@
+pub mod abi;
+pub mod address;
 pub mod auth;
+pub mod block;
 pub mod chain;
+pub mod circuit_breaker;
 pub mod db;
+pub mod events;
+pub mod indexer;
+pub mod metrics;
 pub mod misc;
+pub mod reorg;
+pub mod revert;
 pub mod rlp;
+pub mod rpc_pool;
+pub mod signature;
 pub mod structs;
+pub mod trace;
+pub mod transaction;
+pub mod transfer;
+pub mod webhook;
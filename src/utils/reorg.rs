@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use alloy::{eips::BlockNumberOrTag, providers::Provider, rpc::types::BlockTransactionsKind};
+
+use crate::config::{AppState, REORG_CHECK_DEPTH, REORG_CHECK_INTERVAL_SECS};
+
+use super::{
+    db::{
+        block::delete_non_canonical_blocks,
+        reorg::{
+            fetch_cached_block_numbers, fetch_canonical_block_hash,
+            invalidate_non_canonical_transactions, save_canonical_block, save_reorg_event,
+        },
+    },
+    structs::reorg::CanonicalBlock,
+};
+
+// records the block the node returned for its height as canonical, the cached trxs and
+// headers of other blocks at the height are invalidated, only the reorg check calls this,
+// a fetched trx or block might come from a lagging endpoint or an orphaned block
+async fn observe_block(state: &AppState, block: &CanonicalBlock) -> Result<(), String> {
+    let previous_hash = save_canonical_block(&state.db_connection, state.chain_id(), block)
+        .await
+        .map_err(|e| format!("Failed to save canonical block: {}", e))?;
+
+    let invalidated =
+        invalidate_non_canonical_transactions(&state.db_connection, state.chain_id(), block)
+            .await
+            .map_err(|e| format!("Failed to invalidate block transactions: {}", e))?;
+    delete_non_canonical_blocks(&state.db_connection, state.chain_id(), block)
+        .await
+        .map_err(|e| format!("Failed to invalidate blocks: {}", e))?;
+
+    if let Some(old_block_hash) = previous_hash {
+        save_reorg_event(
+            &state.db_connection,
+            state.chain_id(),
+            block.block_number,
+            &old_block_hash,
+            &block.block_hash,
+            invalidated as i32,
+        )
+        .await
+        .map_err(|e| format!("Failed to save reorg event: {}", e))?;
+
+        println!(
+            "Reorg detected on {} at block {}: {} replaced by {}, {} cached trxs invalidated",
+            state.chain.name, block.block_number, old_block_hash, block.block_hash, invalidated
+        );
+    } else if invalidated > 0 {
+        println!(
+            "{} cached trxs of non canonical blocks invalidated on {} at block {}",
+            invalidated, state.chain.name, block.block_number
+        );
+    }
+
+    Ok(())
+}
+
+// whether another block was recorded as canonical for the height of the block,
+// fetched trxs and headers of such a block are served but not cached
+pub async fn is_non_canonical(
+    state: &AppState,
+    block_number: i32,
+    block_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    let canonical_hash =
+        fetch_canonical_block_hash(&state.db_connection, state.chain_id(), block_number).await?;
+
+    Ok(canonical_hash.is_some_and(|canonical_hash| canonical_hash != block_hash))
+}
+
+// records the canonical blocks of the cached heights within REORG_CHECK_DEPTH of the polled
+// chain head, heights above the head are left for the next check
+pub async fn check_recent_blocks(state: &AppState) -> Result<(), String> {
+    let latest_block_number = state
+        .eth_client
+        .get_block_number()
+        .await
+        .map_err(|e| format!("Failed to fetch latest block number: {}", e))?;
+    let from_block_number = latest_block_number.saturating_sub(*REORG_CHECK_DEPTH) as i32;
+
+    let block_numbers = fetch_cached_block_numbers(
+        &state.db_connection,
+        state.chain_id(),
+        from_block_number,
+        latest_block_number as i32,
+    )
+    .await
+    .map_err(|e| format!("Failed to fetch cached block numbers: {}", e))?;
+
+    for block_number in block_numbers {
+        let node_block = state
+            .eth_client
+            .get_block_by_number(
+                BlockNumberOrTag::Number(block_number as u64),
+                BlockTransactionsKind::Hashes,
+            )
+            .await
+            .map_err(|e| format!("Failed to fetch block: {}", e))?;
+
+        if let Some(node_block) = node_block {
+            observe_block(
+                state,
+                &CanonicalBlock {
+                    block_number,
+                    block_hash: node_block.header.hash.to_string(),
+                },
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// background task checking for reorgs every REORG_CHECK_INTERVAL_SECS
+pub async fn watch_reorgs(state: AppState) {
+    let mut interval = tokio::time::interval(Duration::from_secs(*REORG_CHECK_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(err_msg) = check_recent_blocks(&state).await {
+            println!(
+                "Reorg check failed on {}, error:{}",
+                state.chain.name, err_msg
+            );
+        }
+    }
+}
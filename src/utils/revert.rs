@@ -0,0 +1,247 @@
+use std::str::FromStr;
+
+use alloy::{
+    dyn_abi::JsonAbiExt,
+    eips::BlockId,
+    json_abi::JsonAbi,
+    primitives::{Address, Bytes, U256},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    sol_types::SolValue,
+};
+use futures::{stream, StreamExt};
+use serde_json::Value;
+
+use crate::config::{AppState, FETCH_CONCURRENCY};
+
+use super::{
+    abi::{dyn_sol_value_to_json, fetch_abis},
+    chain::is_transport_failure,
+    db::transaction::{fetch_revert_reason, save_revert_reason},
+    structs::transaction::ResultTransaction,
+};
+
+// Error(string)
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+// Panic(uint256)
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+// the panic codes of the solidity compiler
+fn panic_reason(code: &U256) -> &'static str {
+    match u64::try_from(*code).unwrap_or(u64::MAX) {
+        0x00 => "generic compiler panic",
+        0x01 => "assertion failed",
+        0x11 => "arithmetic underflow or overflow",
+        0x12 => "division or modulo by zero",
+        0x21 => "invalid enum value",
+        0x22 => "invalid storage byte array encoding",
+        0x31 => "pop on empty array",
+        0x32 => "array index out of bounds",
+        0x41 => "out of memory",
+        0x51 => "call to uninitialized function",
+        _ => "unknown panic code",
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+// decodes Error(string), Panic(uint256) and the custom errors of the ABI
+pub fn decode_revert_data(data: &[u8], abi: Option<&JsonAbi>) -> Option<String> {
+    if data.len() < 4 {
+        return None;
+    }
+    let (selector, params) = data.split_at(4);
+
+    if selector == ERROR_SELECTOR {
+        return String::abi_decode(params, false).ok();
+    }
+
+    if selector == PANIC_SELECTOR {
+        let code = U256::abi_decode(params, false).ok()?;
+        return Some(format!("Panic(0x{:x}): {}", code, panic_reason(&code)));
+    }
+
+    let error = abi?
+        .errors()
+        .find(|error| error.selector().as_slice() == selector)?;
+    let values = error.abi_decode_input(params, false).ok()?;
+
+    Some(format!(
+        "{}({})",
+        error.name,
+        values
+            .iter()
+            .map(|value| format_value(&dyn_sol_value_to_json(value)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+// replays the trx at its parent block, returns the revert data
+async fn replay_transaction(trx: &ResultTransaction, state: &AppState) -> Result<Bytes, String> {
+    let block_number = trx
+        .block_number
+        .ok_or("Pending trxs can not be replayed".to_string())?;
+
+    let mut request = TransactionRequest::default()
+        .from(Address::from_str(&trx.from).map_err(|e| e.to_string())?)
+        .input(
+            Bytes::from_str(&trx.input)
+                .map_err(|e| e.to_string())?
+                .into(),
+        )
+        .value(U256::from_str(&trx.value).map_err(|e| e.to_string())?);
+    if let Some(to) = &trx.to {
+        request = request.to(Address::from_str(to).map_err(|e| e.to_string())?);
+    }
+    if let Some(gas_limit) = trx.gas_limit {
+        request = request.gas_limit(gas_limit as u64);
+    }
+
+    // the replay counts towards the circuit breaker, it is skipped while the breaker is open
+    let permit = state
+        .circuit_breaker
+        .try_acquire()
+        .ok_or("Circuit breaker is open".to_string())?;
+    let parent_block = BlockId::number((block_number as u64).saturating_sub(1));
+    let result = state.eth_client.call(&request).block(parent_block).await;
+    permit.record(!is_transport_failure(&result));
+
+    match result {
+        // the trx did not revert at the parent block, e.g. it depended on trxs of its own block
+        Ok(_) => Ok(Bytes::new()),
+        Err(error) => match error.as_error_resp().and_then(|resp| resp.as_revert_data()) {
+            Some(revert_data) => Ok(revert_data),
+            None => Err(format!("Failed to replay trx: {}", error)),
+        },
+    }
+}
+
+async fn resolve_revert_reason(trx: &mut ResultTransaction, state: &AppState) {
+    // the revert reason is resolved only once per trx
+    match fetch_revert_reason(
+        &state.db_connection,
+        state.chain_id(),
+        &trx.transaction_hash,
+    )
+    .await
+    {
+        Ok(Some((revert_reason, revert_data))) => {
+            trx.revert_reason = revert_reason;
+            trx.revert_data = Some(revert_data);
+            return;
+        }
+        Ok(None) => {}
+        Err(error) => {
+            println!("Failed to fetch revert reason from db: {}", error);
+            return;
+        }
+    }
+
+    let revert_data = match replay_transaction(trx, state).await {
+        Ok(revert_data) => revert_data,
+        Err(err_msg) => {
+            println!(
+                "Failed to resolve revert reason of {}, error:{}",
+                trx.transaction_hash, err_msg
+            );
+            return;
+        }
+    };
+
+    let abis = fetch_abis(state, trx.to.clone().into_iter().collect()).await;
+    let abi = trx.to.as_ref().and_then(|to| abis.get(&to.to_lowercase()));
+
+    trx.revert_reason = decode_revert_data(&revert_data, abi);
+    trx.revert_data = Some(revert_data.to_string());
+
+    if let Err(error) = save_revert_reason(
+        &state.db_connection,
+        state.chain_id(),
+        &trx.transaction_hash,
+        &trx.revert_reason,
+        &revert_data.to_string(),
+    )
+    .await
+    {
+        println!("Failed to save revert reason in db: {}", error);
+    }
+}
+
+// resolves the revert reason of the failed trxs, which were not resolved yet
+pub async fn resolve_revert_reasons(state: &AppState, transactions: &mut [ResultTransaction]) {
+    stream::iter(
+        transactions
+            .iter_mut()
+            .filter(|trx| trx.transaction_status == Some(0) && trx.revert_data.is_none()),
+    )
+    .for_each_concurrent(*FETCH_CONCURRENCY, |trx| async move {
+        resolve_revert_reason(trx, state).await
+    })
+    .await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_selector(selector: &[u8], params: Vec<u8>) -> Vec<u8> {
+        [selector.to_vec(), params].concat()
+    }
+
+    #[test]
+    fn test_decode_error_string() {
+        let data = with_selector(
+            &ERROR_SELECTOR,
+            "Insufficient balance".to_string().abi_encode(),
+        );
+
+        assert_eq!(
+            decode_revert_data(&data, None),
+            Some("Insufficient balance".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decode_panic() {
+        let data = with_selector(&PANIC_SELECTOR, U256::from(0x11).abi_encode());
+
+        assert_eq!(
+            decode_revert_data(&data, None),
+            Some("Panic(0x11): arithmetic underflow or overflow".to_string())
+        );
+    }
+
+    #[test]
+    fn test_decode_custom_error() {
+        let abi: JsonAbi = serde_json::from_str(
+            r#"[{
+                "type": "error",
+                "name": "InsufficientBalance",
+                "inputs": [
+                    { "name": "available", "type": "uint256" },
+                    { "name": "required", "type": "uint256" }
+                ]
+            }]"#,
+        )
+        .expect("Invalid test abi");
+        let error = abi.errors().next().expect("Missing test error");
+        let data = with_selector(
+            error.selector().as_slice(),
+            (U256::from(1), U256::from(2)).abi_encode_params(),
+        );
+
+        assert_eq!(
+            decode_revert_data(&data, Some(&abi)),
+            Some("InsufficientBalance(1, 2)".to_string())
+        );
+        // custom errors are only decoded with a registered ABI
+        assert_eq!(decode_revert_data(&data, None), None);
+        assert_eq!(decode_revert_data(&[], None), None);
+    }
+}
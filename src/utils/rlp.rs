@@ -16,7 +16,7 @@ pub fn decode_rlp_encoded_list(rlp_hex: &String) -> Result<Vec<String>, Error> {
     let transaction_hashes: Vec<Vec<u8>> = rlp.as_list().expect("Failed to decode RLP list");
 
     // Print the transaction hashes as hex strings
-    for (_, hash) in transaction_hashes.iter().enumerate() {
+    for hash in transaction_hashes.iter() {
         decoded_hashes.push(hex::encode(hash));
     }
 
@@ -35,7 +35,7 @@ pub fn encode_hexes_to_rlp(hashes: &Vec<String>) -> Result<String, String> {
         let hash_trimmed = hash.trim_start_matches("0x");
 
         // Convert the hash string to bytes
-        if let Ok(bytes) = Vec::from_hex(&hash_trimmed) {
+        if let Ok(bytes) = Vec::from_hex(hash_trimmed) {
             stream.append(&bytes);
         } else {
             return Err("Unable to encode bytes".to_string());
@@ -0,0 +1,1090 @@
+use std::{
+    collections::VecDeque,
+    path::Path,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, SystemTime},
+};
+
+use alloy::{
+    providers::{Provider, RootProvider},
+    rpc::{
+        client::{BuiltInConnectionString, RpcClient},
+        json_rpc::{RequestPacket, ResponsePacket},
+    },
+    transports::{
+        http::Http, BoxTransport, RpcError, Transport, TransportError, TransportErrorKind,
+        TransportFut,
+    },
+};
+use futures::future::join_all;
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    Client, StatusCode, Url,
+};
+use time::OffsetDateTime;
+use tower::Service;
+
+use crate::config::{
+    AppState, RpcEndpointConfig, RPC_CALL_TIMEOUT_MS, RPC_ERROR_WINDOW,
+    RPC_HEALTH_CHECK_INTERVAL_SECS, RPC_MAX_BLOCK_LAG, RPC_MAX_ERROR_RATE, RPC_NODE_ERROR_RETRIES,
+    RPC_RATE_LIMIT_RETRIES, RPC_RETRY_BASE_DELAY_MS, RPC_RETRY_MAX_DELAY_MS, RPC_TIMEOUT_RETRIES,
+};
+
+use super::structs::{
+    chain::{RpcCallError, RpcErrorKind},
+    rpc_pool::RpcEndpointStatus,
+};
+
+// the error rate is only trusted once the window holds this many calls
+const MIN_ERROR_RATE_CALLS: usize = 5;
+
+// JSON-RPC error codes used by providers for exceeded request limits
+const RATE_LIMIT_ERROR_CODES: [i64; 2] = [-32005, 429];
+
+// the retries made so far for each kind of failure
+#[derive(Debug, Default)]
+struct RetryBudget {
+    rate_limited: u32,
+    timeout: u32,
+    node_error: u32,
+}
+impl RetryBudget {
+    // counts a retry of the kind and returns how many retries of it were made before,
+    // None once the retries of the kind are used up
+    fn next_retry(&mut self, kind: RpcErrorKind) -> Option<u32> {
+        let (retries, max_retries) = match kind {
+            RpcErrorKind::RateLimited => (&mut self.rate_limited, *RPC_RATE_LIMIT_RETRIES),
+            RpcErrorKind::Timeout => (&mut self.timeout, *RPC_TIMEOUT_RETRIES),
+            RpcErrorKind::NodeError => (&mut self.node_error, *RPC_NODE_ERROR_RETRIES),
+            RpcErrorKind::Rejected => return None,
+        };
+        if *retries >= max_retries {
+            return None;
+        }
+
+        *retries += 1;
+        Some(*retries - 1)
+    }
+}
+
+// exponential backoff capped at RPC_RETRY_MAX_DELAY_MS, the jitter between 0 and 1 spreads
+// the delay over its upper half so concurrent calls do not retry at the same time,
+// a longer Retry-After asked for by the node is honoured up to RPC_RETRY_MAX_DELAY_MS
+fn retry_delay(retry: u32, retry_after: Option<Duration>, jitter: f64) -> Duration {
+    let max_delay = Duration::from_millis(*RPC_RETRY_MAX_DELAY_MS);
+    let backoff = RPC_RETRY_BASE_DELAY_MS
+        .saturating_mul(1 << retry.min(20))
+        .min(*RPC_RETRY_MAX_DELAY_MS);
+    let backoff = Duration::from_millis((backoff as f64 * (0.5 + jitter / 2.0)) as u64);
+
+    retry_after.map_or(backoff, |retry_after| {
+        retry_after.max(backoff).min(max_delay)
+    })
+}
+
+// the Retry-After header holds either a number of seconds or an http date
+fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => httpdate::parse_http_date(value)
+            .ok()
+            .map(|date| date.duration_since(SystemTime::now()).unwrap_or_default()),
+    }
+}
+
+fn status_error_kind(status: StatusCode) -> RpcErrorKind {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS => RpcErrorKind::RateLimited,
+        StatusCode::REQUEST_TIMEOUT | StatusCode::GATEWAY_TIMEOUT => RpcErrorKind::Timeout,
+        status if status.is_server_error() => RpcErrorKind::NodeError,
+        _ => RpcErrorKind::Rejected,
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct EndpointHealth {
+    block_number: Option<u64>,
+    block_lag: Option<u64>,
+    recent_errors: VecDeque<bool>, // the outcome of the last RPC_ERROR_WINDOW calls, true when failed
+    calls: u64,
+    errors: u64,
+    last_error: Option<String>,
+    last_checked_at: Option<i64>,
+    current_weight: i64, // smooth weighted round robin state
+    disconnected: bool,  // the endpoint could not be connected yet, it is never called
+}
+impl EndpointHealth {
+    fn record(&mut self, error: Option<String>) {
+        self.calls += 1;
+        self.recent_errors.push_back(error.is_some());
+        while self.recent_errors.len() > *RPC_ERROR_WINDOW {
+            self.recent_errors.pop_front();
+        }
+        if error.is_some() {
+            self.errors += 1;
+            self.last_error = error;
+        }
+    }
+
+    fn error_rate(&self) -> f64 {
+        if self.recent_errors.is_empty() {
+            return 0.0;
+        }
+        let errors = self.recent_errors.iter().filter(|failed| **failed).count();
+        errors as f64 / self.recent_errors.len() as f64
+    }
+
+    fn is_healthy(&self) -> bool {
+        let lagging = self.block_lag.is_some_and(|lag| lag > *RPC_MAX_BLOCK_LAG);
+        let failing = self.recent_errors.len() >= MIN_ERROR_RATE_CALLS
+            && self.error_rate() > *RPC_MAX_ERROR_RATE;
+
+        !self.disconnected && !lagging && !failing
+    }
+}
+
+#[derive(Debug, Clone)]
+enum EndpointTransport {
+    // http urls are called directly, the alloy http transport drops the response headers
+    // and with them the Retry-After header of rate limits
+    Http { url: Url, client: Client },
+    // ws/wss urls and ipc socket paths keep their connection open, which subscriptions reuse
+    Connected(BoxTransport),
+}
+
+// the transport of an endpoint that answered with the chain id of its chain
+#[derive(Debug, Clone)]
+struct EndpointConnection {
+    transport: EndpointTransport,
+    provider: RootProvider<BoxTransport>, // used for the health checks and subscriptions
+}
+impl EndpointConnection {
+    async fn open(config: &RpcEndpointConfig, chain_id: u64) -> Result<EndpointConnection, String> {
+        let transport = match Url::parse(&config.url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {
+                // every call, including the health checks, times out after RPC_CALL_TIMEOUT_MS
+                let client = Client::builder()
+                    .timeout(Duration::from_millis(*RPC_CALL_TIMEOUT_MS))
+                    .build()
+                    .map_err(|e| format!("Failed to build http client: {}", e))?;
+                EndpointTransport::Http { url, client }
+            }
+            _ => {
+                let connection: BuiltInConnectionString = config
+                    .url
+                    .parse()
+                    .map_err(|e| format!("Invalid rpc url {}: {}", redact_url(&config.url), e))?;
+                // a node that never answers the dial must not hold up the startup
+                let connect_timeout = Duration::from_millis(*RPC_CALL_TIMEOUT_MS);
+                let transport = tokio::time::timeout(connect_timeout, connection.connect_boxed())
+                    .await
+                    .map_err(|_| {
+                        format!(
+                            "No connection to {} within {}ms",
+                            redact_url(&config.url),
+                            connect_timeout.as_millis()
+                        )
+                    })?
+                    .map_err(|e| {
+                        format!("Failed to connect to {}: {}", redact_url(&config.url), e)
+                    })?;
+                EndpointTransport::Connected(transport)
+            }
+        };
+
+        let provider_transport = match &transport {
+            EndpointTransport::Http { url, client } => {
+                Http::with_client(client.clone(), url.clone()).boxed()
+            }
+            EndpointTransport::Connected(transport) => transport.clone(),
+        };
+        let provider = RootProvider::new(RpcClient::new(provider_transport, false));
+
+        // an endpoint of another chain would serve wrong trxs, it is never called
+        let call_timeout = Duration::from_millis(*RPC_CALL_TIMEOUT_MS);
+        let node_chain_id = tokio::time::timeout(call_timeout, provider.get_chain_id())
+            .await
+            .map_err(|_| format!("No chain id within {}ms", call_timeout.as_millis()))?
+            .map_err(|e| format!("Failed to fetch chain id: {}", e))?;
+        if node_chain_id != chain_id {
+            return Err(format!(
+                "The node serves chain id {} instead of {}",
+                node_chain_id, chain_id
+            ));
+        }
+
+        Ok(EndpointConnection {
+            transport,
+            provider,
+        })
+    }
+
+    async fn call(&self, request: &RequestPacket) -> Result<ResponsePacket, RpcCallError> {
+        let (response, retry_after) = match &self.transport {
+            EndpointTransport::Http { url, client } => post_request(client, url, request).await?,
+            EndpointTransport::Connected(transport) => {
+                let call_timeout = Duration::from_millis(*RPC_CALL_TIMEOUT_MS);
+                let response =
+                    tokio::time::timeout(call_timeout, transport.clone().call(request.clone()))
+                        .await
+                        .map_err(|_| RpcCallError {
+                            kind: RpcErrorKind::Timeout,
+                            message: format!("No response within {}ms", call_timeout.as_millis()),
+                            retry_after: None,
+                        })?
+                        .map_err(|error| RpcCallError {
+                            kind: transport_error_kind(&error),
+                            message: error.to_string(),
+                            retry_after: None,
+                        })?;
+                (response, None)
+            }
+        };
+
+        // some providers answer a rate limit with a JSON-RPC error instead of a 429
+        if let Some(error) = response
+            .iter_errors()
+            .find(|error| RATE_LIMIT_ERROR_CODES.contains(&error.code))
+        {
+            return Err(RpcCallError {
+                kind: RpcErrorKind::RateLimited,
+                message: error.message.to_string(),
+                retry_after,
+            });
+        }
+
+        Ok(response)
+    }
+}
+
+#[derive(Debug)]
+struct RpcEndpoint {
+    config: RpcEndpointConfig,
+    // None while the endpoint could not be connected, the health checks retry it
+    connection: Mutex<Option<EndpointConnection>>,
+}
+impl RpcEndpoint {
+    fn connection(&self) -> Option<EndpointConnection> {
+        self.connection.lock().unwrap().clone()
+    }
+}
+
+// posts the request to the http endpoint, returning the response with its Retry-After
+async fn post_request(
+    client: &Client,
+    url: &Url,
+    request: &RequestPacket,
+) -> Result<(ResponsePacket, Option<Duration>), RpcCallError> {
+    let failed = |kind: RpcErrorKind, message: String| RpcCallError {
+        kind,
+        message,
+        retry_after: None,
+    };
+    let reqwest_error_kind = |error: &reqwest::Error| {
+        if error.is_timeout() {
+            RpcErrorKind::Timeout
+        } else {
+            RpcErrorKind::NodeError
+        }
+    };
+
+    let response = client
+        .post(url.clone())
+        .json(request)
+        .send()
+        .await
+        .map_err(|error| failed(reqwest_error_kind(&error), error.to_string()))?;
+    let status = response.status();
+    let retry_after = parse_retry_after(response.headers());
+    let body = response
+        .bytes()
+        .await
+        .map_err(|error| failed(reqwest_error_kind(&error), error.to_string()))?;
+
+    if !status.is_success() {
+        return Err(RpcCallError {
+            kind: status_error_kind(status),
+            message: format!("HTTP {}: {}", status, String::from_utf8_lossy(&body)),
+            retry_after,
+        });
+    }
+
+    let response: ResponsePacket = serde_json::from_slice(&body).map_err(|error| {
+        failed(
+            RpcErrorKind::Rejected,
+            format!("Invalid JSON-RPC response: {}", error),
+        )
+    })?;
+
+    Ok((response, retry_after))
+}
+
+// a dropped ws or ipc connection is a node error, alloy reconnects it in the background
+// and the health checks dial it again once alloy gave up
+fn transport_error_kind(error: &TransportError) -> RpcErrorKind {
+    match error {
+        RpcError::Transport(TransportErrorKind::HttpError(error)) => {
+            StatusCode::from_u16(error.status).map_or(RpcErrorKind::NodeError, status_error_kind)
+        }
+        RpcError::Transport(_) => RpcErrorKind::NodeError,
+        _ => RpcErrorKind::Rejected,
+    }
+}
+
+// the ws or ipc backend of the transport stopped and is not reconnected anymore
+fn is_backend_gone(error: &TransportError) -> bool {
+    matches!(
+        error,
+        RpcError::Transport(
+            TransportErrorKind::BackendGone | TransportErrorKind::PubsubUnavailable
+        )
+    )
+}
+
+#[derive(Debug)]
+struct RpcPoolInner {
+    chain_id: u64, // the chain id the endpoints are checked against when connecting
+    endpoints: Vec<RpcEndpoint>,
+    health: Mutex<Vec<EndpointHealth>>,
+}
+
+// a transport spreading the calls over the rpc endpoints of a chain, a call failing
+// on one endpoint is sent to the next one, unhealthy endpoints are tried last
+#[derive(Debug, Clone)]
+pub struct RpcPool(Arc<RpcPoolInner>);
+impl RpcPool {
+    // connects to the endpoints and checks their chain id, ws/wss urls and ipc socket paths
+    // open their connection here, an endpoint failing to connect starts unhealthy and is
+    // connected by the health checks, fails when no endpoint could be connected
+    pub async fn connect(
+        chain_id: u64,
+        endpoints: &[RpcEndpointConfig],
+    ) -> Result<RpcPool, String> {
+        let connections = join_all(
+            endpoints
+                .iter()
+                .map(|config| EndpointConnection::open(config, chain_id)),
+        )
+        .await;
+
+        let mut rpc_endpoints = vec![];
+        let mut health = vec![];
+        let mut errors = vec![];
+        for (config, connection) in endpoints.iter().zip(connections) {
+            let mut endpoint_health = EndpointHealth::default();
+            let connection = match connection {
+                Ok(connection) => Some(connection),
+                Err(err_msg) => {
+                    println!(
+                        "Rpc endpoint {} is unavailable, retrying at the next health check, error:{}",
+                        redact_url(&config.url),
+                        err_msg
+                    );
+                    endpoint_health.disconnected = true;
+                    endpoint_health.record(Some(err_msg.clone()));
+                    errors.push(format!("{}: {}", redact_url(&config.url), err_msg));
+                    None
+                }
+            };
+
+            rpc_endpoints.push(RpcEndpoint {
+                config: config.clone(),
+                connection: Mutex::new(connection),
+            });
+            health.push(endpoint_health);
+        }
+
+        if errors.len() == rpc_endpoints.len() {
+            return Err(format!("No rpc endpoint is usable, {}", errors.join(", ")));
+        }
+
+        Ok(RpcPool(Arc::new(RpcPoolInner {
+            chain_id,
+            endpoints: rpc_endpoints,
+            health: Mutex::new(health),
+        })))
+    }
+
+    async fn send(self, request: RequestPacket) -> Result<ResponsePacket, TransportError> {
+        let mut budget = RetryBudget::default();
+
+        loop {
+            let order = {
+                let mut health = self.0.health.lock().unwrap();
+                let configs: Vec<&RpcEndpointConfig> = self
+                    .0
+                    .endpoints
+                    .iter()
+                    .map(|endpoint| &endpoint.config)
+                    .collect();
+                order_endpoints(&configs, &mut health)
+            };
+
+            let mut errors: Vec<RpcCallError> = vec![];
+            for index in order {
+                let endpoint = &self.0.endpoints[index];
+                let Some(connection) = endpoint.connection() else {
+                    errors.push(RpcCallError {
+                        kind: RpcErrorKind::NodeError,
+                        message: format!("{} is not connected", redact_url(&endpoint.config.url)),
+                        retry_after: None,
+                    });
+                    continue;
+                };
+
+                // JSON-RPC error responses are answers of the node, only failed calls fail over
+                let result = connection.call(&request).await;
+                self.0.health.lock().unwrap()[index]
+                    .record(result.as_ref().err().map(|error| error.to_string()));
+
+                match result {
+                    Ok(response) => return Ok(response),
+                    Err(error) => {
+                        println!(
+                            "Rpc endpoint {} failed, error:{}",
+                            redact_url(&endpoint.config.url),
+                            error
+                        );
+                        errors.push(error);
+                    }
+                }
+            }
+
+            // the first retryable failure decides how the call is retried
+            let error = match errors
+                .iter()
+                .position(|error| error.kind != RpcErrorKind::Rejected)
+            {
+                Some(position) => errors.swap_remove(position),
+                None => match errors.pop() {
+                    Some(error) => error,
+                    None => return Err(TransportErrorKind::custom_str("No rpc endpoints")),
+                },
+            };
+
+            match budget.next_retry(error.kind) {
+                Some(retry) => {
+                    let delay = retry_delay(retry, error.retry_after, rand::random::<f64>());
+                    println!(
+                        "Retrying rpc call in {}ms after it {}",
+                        delay.as_millis(),
+                        error.kind
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                None => return Err(TransportErrorKind::custom(error)),
+            }
+        }
+    }
+
+    // fetches the head block of every endpoint, recording its lag behind the most advanced one,
+    // the endpoints that are not connected yet are connected first
+    pub async fn check_health(&self) {
+        let block_numbers = join_all(self.0.endpoints.iter().map(|endpoint| async {
+            let connection = match endpoint.connection() {
+                Some(connection) => connection,
+                None => {
+                    let connection =
+                        EndpointConnection::open(&endpoint.config, self.0.chain_id).await?;
+                    println!(
+                        "Rpc endpoint {} connected",
+                        redact_url(&endpoint.config.url)
+                    );
+                    *endpoint.connection.lock().unwrap() = Some(connection.clone());
+                    connection
+                }
+            };
+
+            // ws and ipc calls have no client timeout of their own
+            let call_timeout = Duration::from_millis(*RPC_CALL_TIMEOUT_MS);
+            match tokio::time::timeout(call_timeout, connection.provider.get_block_number()).await {
+                Ok(Err(error)) if is_backend_gone(&error) => {
+                    // alloy gave up reconnecting the ws or ipc connection, the next check dials it
+                    *endpoint.connection.lock().unwrap() = None;
+                    Err(error.to_string())
+                }
+                Ok(block_number) => block_number.map_err(|error| error.to_string()),
+                Err(_) => Err(format!("No response within {}ms", call_timeout.as_millis())),
+            }
+        }))
+        .await;
+        let best_block_number = block_numbers
+            .iter()
+            .filter_map(|block_number| block_number.as_ref().ok())
+            .max()
+            .copied();
+        let checked_at = OffsetDateTime::now_utc().unix_timestamp();
+
+        let mut health = self.0.health.lock().unwrap();
+        for ((endpoint_health, endpoint), block_number) in health
+            .iter_mut()
+            .zip(self.0.endpoints.iter())
+            .zip(block_numbers)
+        {
+            endpoint_health.last_checked_at = Some(checked_at);
+            endpoint_health.disconnected = endpoint.connection().is_none();
+
+            match block_number {
+                Ok(block_number) => {
+                    endpoint_health.block_number = Some(block_number);
+                    endpoint_health.block_lag =
+                        best_block_number.map(|best| best.saturating_sub(block_number));
+                    endpoint_health.record(None);
+                }
+                Err(error) => {
+                    endpoint_health.block_lag = None;
+                    endpoint_health.record(Some(error));
+                }
+            }
+        }
+    }
+
+    // a provider over the preferred connected ws or ipc endpoint, None when the chain has http
+    // endpoints only
+    pub fn subscription_provider(&self) -> Option<RootProvider<BoxTransport>> {
+        self.0
+            .endpoints
+            .iter()
+            .filter_map(|endpoint| {
+                let connection = endpoint.connection()?;
+                connection
+                    .provider
+                    .client()
+                    .pubsub_frontend()
+                    .is_some()
+                    .then_some((endpoint.config.priority, connection.provider))
+            })
+            .min_by_key(|(priority, _)| *priority)
+            .map(|(_, provider)| provider)
+    }
+
+    pub fn status(&self) -> Vec<RpcEndpointStatus> {
+        let health = self.0.health.lock().unwrap();
+
+        self.0
+            .endpoints
+            .iter()
+            .zip(health.iter())
+            .map(|(endpoint, endpoint_health)| RpcEndpointStatus {
+                url: redact_url(&endpoint.config.url),
+                priority: endpoint.config.priority,
+                weight: endpoint.config.weight,
+                healthy: endpoint_health.is_healthy(),
+                block_number: endpoint_health.block_number,
+                block_lag: endpoint_health.block_lag,
+                error_rate: endpoint_health.error_rate(),
+                calls: endpoint_health.calls,
+                errors: endpoint_health.errors,
+                last_error: endpoint_health.last_error.clone(),
+                last_checked_at: endpoint_health.last_checked_at,
+            })
+            .collect()
+    }
+}
+
+impl Service<RequestPacket> for RpcPool {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        Box::pin(self.clone().send(request))
+    }
+}
+
+// the order in which the endpoints are tried for a call: the first one is picked by smooth
+// weighted round robin among the healthy endpoints of the best priority, followed by the
+// other healthy endpoints and finally the unhealthy ones, each by priority and weight
+fn order_endpoints(configs: &[&RpcEndpointConfig], health: &mut [EndpointHealth]) -> Vec<usize> {
+    let by_priority = |indexes: &mut Vec<usize>| {
+        indexes.sort_by_key(|index| (configs[*index].priority, u32::MAX - configs[*index].weight))
+    };
+
+    let (mut healthy, mut unhealthy): (Vec<usize>, Vec<usize>) =
+        (0..configs.len()).partition(|index| health[*index].is_healthy());
+    by_priority(&mut healthy);
+    by_priority(&mut unhealthy);
+
+    if let Some(best_priority) = healthy.first().map(|index| configs[*index].priority) {
+        let best: Vec<usize> = healthy
+            .iter()
+            .copied()
+            .filter(|index| configs[*index].priority == best_priority)
+            .collect();
+        let total_weight: i64 = best.iter().map(|index| configs[*index].weight as i64).sum();
+
+        for index in best.iter() {
+            health[*index].current_weight += configs[*index].weight as i64;
+        }
+        // the first endpoint wins ties, so equal weights keep the configured order
+        let picked = best
+            .iter()
+            .copied()
+            .reduce(|picked, index| {
+                if health[index].current_weight > health[picked].current_weight {
+                    index
+                } else {
+                    picked
+                }
+            })
+            .unwrap_or(best[0]);
+        health[picked].current_weight -= total_weight;
+
+        healthy.retain(|index| *index != picked);
+        healthy.insert(0, picked);
+    }
+
+    healthy.into_iter().chain(unhealthy).collect()
+}
+
+// hides the path and query of the url, which might contain an api key
+fn redact_url(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(parsed) if parsed.path().len() > 1 || parsed.query().is_some() => format!(
+            "{}://{}/***",
+            parsed.scheme(),
+            parsed.host_str().unwrap_or_default()
+        ),
+        Ok(parsed) => format!(
+            "{}://{}",
+            parsed.scheme(),
+            parsed.host_str().unwrap_or_default()
+        ),
+        // ipc socket paths contain no api key
+        Err(_) if Path::new(url).is_absolute() => url.to_string(),
+        Err(_) => "***".to_string(),
+    }
+}
+
+// background task checking the rpc endpoints every RPC_HEALTH_CHECK_INTERVAL_SECS
+pub async fn watch_rpc_endpoints(state: AppState) {
+    let mut interval = tokio::time::interval(Duration::from_secs(*RPC_HEALTH_CHECK_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+        state.rpc_pool.check_health().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::HeaderValue, response::IntoResponse, routing::post, Json, Router};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::utils::structs::chain::ChainError;
+
+    // the fixture nodes answer every call with 0x10, eth_chainId included
+    const NODE_CHAIN_ID: u64 = 16;
+
+    fn endpoint(priority: u32, weight: u32) -> RpcEndpointConfig {
+        RpcEndpointConfig {
+            url: "http://localhost".to_string(),
+            priority,
+            weight,
+        }
+    }
+
+    // a node answering every call with block number 16
+    async fn fixture_node() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind test node");
+        let address = listener
+            .local_addr()
+            .expect("Failed to read test node address");
+        serve_fixture_node(listener);
+
+        format!("http://{}", address)
+    }
+
+    fn serve_fixture_node(listener: tokio::net::TcpListener) {
+        let app = Router::new().route(
+            "/",
+            post(|Json(request): Json<Value>| async move {
+                Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": "0x10" }))
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await });
+    }
+
+    // a node failing the first calls with the status, returns the url and the call counter
+    async fn fixture_failing_node(
+        failures: usize,
+        status: StatusCode,
+    ) -> (String, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let node_calls = calls.clone();
+        let app = Router::new().route(
+            "/",
+            post(move |Json(request): Json<Value>| async move {
+                // the chain id check of the pool is answered and not counted
+                if request["method"] != "eth_chainId"
+                    && node_calls.fetch_add(1, Ordering::SeqCst) < failures
+                {
+                    let mut response = (status, "slow down").into_response();
+                    response
+                        .headers_mut()
+                        .insert(RETRY_AFTER, HeaderValue::from_static("0"));
+                    return response;
+                }
+                Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": "0x10" }))
+                    .into_response()
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind test node");
+        let address = listener
+            .local_addr()
+            .expect("Failed to read test node address");
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        (format!("http://{}", address), calls)
+    }
+
+    async fn fixture_provider(node_url: String) -> RootProvider<RpcPool> {
+        let rpc_pool = RpcPool::connect(
+            NODE_CHAIN_ID,
+            &[RpcEndpointConfig {
+                url: node_url,
+                ..endpoint(0, 1)
+            }],
+        )
+        .await
+        .expect("Failed to create rpc pool");
+
+        RootProvider::new(RpcClient::new(rpc_pool, false))
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let base = *RPC_RETRY_BASE_DELAY_MS;
+
+        assert_eq!(retry_delay(0, None, 1.0), Duration::from_millis(base));
+        assert_eq!(retry_delay(0, None, 0.0), Duration::from_millis(base / 2));
+        assert_eq!(retry_delay(2, None, 1.0), Duration::from_millis(base * 4));
+        assert_eq!(
+            retry_delay(30, None, 1.0),
+            Duration::from_millis(*RPC_RETRY_MAX_DELAY_MS)
+        );
+        // the Retry-After of the node wins over a shorter backoff, up to the max delay
+        assert_eq!(
+            retry_delay(0, Some(Duration::from_millis(base * 3)), 1.0),
+            Duration::from_millis(base * 3)
+        );
+        assert_eq!(
+            retry_delay(0, Some(Duration::from_secs(3600)), 1.0),
+            Duration::from_millis(*RPC_RETRY_MAX_DELAY_MS)
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("3"));
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(3)));
+
+        // a date in the past asks for no wait
+        headers.insert(
+            RETRY_AFTER,
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_retry_budget_per_kind() {
+        let mut budget = RetryBudget::default();
+
+        for retry in 0..*RPC_TIMEOUT_RETRIES {
+            assert_eq!(budget.next_retry(RpcErrorKind::Timeout), Some(retry));
+        }
+        assert_eq!(budget.next_retry(RpcErrorKind::Timeout), None);
+        // the other kinds keep their own budget
+        assert_eq!(budget.next_retry(RpcErrorKind::RateLimited), Some(0));
+        assert_eq!(budget.next_retry(RpcErrorKind::Rejected), None);
+    }
+
+    #[tokio::test]
+    async fn test_retries_rate_limited_call() {
+        let (node_url, calls) = fixture_failing_node(2, StatusCode::TOO_MANY_REQUESTS).await;
+
+        let block_number = fixture_provider(node_url)
+            .await
+            .get_block_number()
+            .await
+            .expect("Failed to retry rate limited call");
+
+        assert_eq!(block_number, 16);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_call_is_not_retried() {
+        let (node_url, calls) = fixture_failing_node(1, StatusCode::UNAUTHORIZED).await;
+
+        let error = fixture_provider(node_url)
+            .await
+            .get_block_number()
+            .await
+            .expect_err("Rejected call should fail");
+
+        assert!(matches!(
+            ChainError::from(error),
+            ChainError::Rpc {
+                kind: RpcErrorKind::Rejected,
+                ..
+            }
+        ));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_order_endpoints_by_weight_and_priority() {
+        let configs = [endpoint(0, 3), endpoint(0, 1), endpoint(1, 1)];
+        let configs: Vec<&RpcEndpointConfig> = configs.iter().collect();
+        let mut health = vec![EndpointHealth::default(); 3];
+
+        let orders: Vec<Vec<usize>> = (0..4)
+            .map(|_| order_endpoints(&configs, &mut health))
+            .collect();
+
+        // the load of the best priority is shared 3:1, the backup endpoint is always last
+        assert_eq!(
+            orders,
+            vec![vec![0, 1, 2], vec![0, 1, 2], vec![1, 0, 2], vec![0, 1, 2]]
+        );
+    }
+
+    #[test]
+    fn test_order_endpoints_tries_unhealthy_last() {
+        let configs = [endpoint(0, 1), endpoint(1, 1)];
+        let configs: Vec<&RpcEndpointConfig> = configs.iter().collect();
+        let mut health = vec![EndpointHealth::default(); 2];
+
+        for _ in 0..MIN_ERROR_RATE_CALLS {
+            health[0].record(Some("connection refused".to_string()));
+        }
+        assert_eq!(order_endpoints(&configs, &mut health), vec![1, 0]);
+
+        // a lagging endpoint is unhealthy as well
+        health[0] = EndpointHealth::default();
+        health[1].block_lag = Some(*RPC_MAX_BLOCK_LAG + 1);
+        assert_eq!(order_endpoints(&configs, &mut health), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_redact_url() {
+        assert_eq!(
+            redact_url("https://sepolia.infura.io/v3/secret"),
+            "https://sepolia.infura.io/***"
+        );
+        assert_eq!(redact_url("http://localhost:8545"), "http://localhost");
+        assert_eq!(redact_url("/tmp/geth.ipc"), "/tmp/geth.ipc");
+    }
+
+    // a node on an ipc socket answering every call with block number 16
+    fn fixture_ipc_node(path: &str) {
+        let _ = std::fs::remove_file(path);
+        let listener = tokio::net::UnixListener::bind(path).expect("Failed to bind test ipc node");
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+                    let mut buffer: Vec<u8> = vec![];
+                    let mut chunk = [0u8; 4096];
+                    while let Ok(read) = socket.read(&mut chunk).await {
+                        if read == 0 {
+                            break;
+                        }
+                        buffer.extend_from_slice(&chunk[..read]);
+
+                        // the requests are written back to back, answer every complete one
+                        let mut requests =
+                            serde_json::Deserializer::from_slice(&buffer).into_iter::<Value>();
+                        let mut answers = vec![];
+                        for request in requests.by_ref() {
+                            match request {
+                                Ok(request) => answers.push(json!({
+                                    "jsonrpc": "2.0", "id": request["id"], "result": "0x10"
+                                })),
+                                Err(_) => break,
+                            }
+                        }
+                        let consumed = requests.byte_offset();
+                        buffer.drain(..consumed);
+
+                        for answer in answers {
+                            let _ = socket.write_all(answer.to_string().as_bytes()).await;
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn test_calls_ipc_endpoint() {
+        let path = format!("/tmp/eth_fetcher_test_{}.ipc", std::process::id());
+        fixture_ipc_node(&path);
+
+        let provider = fixture_provider(path.clone()).await;
+        let block_number = provider
+            .get_block_number()
+            .await
+            .expect("Failed to call ipc endpoint");
+
+        assert_eq!(block_number, 16);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_connects_ipc_endpoint() {
+        let path = format!("/tmp/eth_fetcher_test_{}_late.ipc", std::process::id());
+        let _ = std::fs::remove_file(&path);
+
+        let rpc_pool = RpcPool::connect(
+            NODE_CHAIN_ID,
+            &[
+                RpcEndpointConfig {
+                    url: path.clone(),
+                    ..endpoint(0, 1)
+                },
+                RpcEndpointConfig {
+                    url: fixture_node().await,
+                    ..endpoint(1, 1)
+                },
+            ],
+        )
+        .await
+        .expect("Failed to create rpc pool");
+        assert!(!rpc_pool.status()[0].healthy);
+        assert!(rpc_pool.subscription_provider().is_none());
+
+        fixture_ipc_node(&path);
+        rpc_pool.check_health().await;
+
+        assert_eq!(rpc_pool.status()[0].block_number, Some(16));
+        assert!(rpc_pool.subscription_provider().is_some());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn test_is_backend_gone() {
+        assert!(is_backend_gone(&TransportErrorKind::backend_gone()));
+        assert!(!is_backend_gone(&TransportErrorKind::custom_str(
+            "connection refused"
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_to_next_endpoint() {
+        let node_url = fixture_node().await;
+        let rpc_pool = RpcPool::connect(
+            NODE_CHAIN_ID,
+            &[
+                RpcEndpointConfig {
+                    url: "http://127.0.0.1:1".to_string(),
+                    ..endpoint(0, 1)
+                },
+                RpcEndpointConfig {
+                    url: node_url,
+                    ..endpoint(1, 1)
+                },
+            ],
+        )
+        .await
+        .expect("Failed to create rpc pool");
+        let provider: RootProvider<RpcPool> =
+            RootProvider::new(RpcClient::new(rpc_pool.clone(), false));
+
+        let block_number = provider
+            .get_block_number()
+            .await
+            .expect("Failed to fail over");
+        assert_eq!(block_number, 16);
+
+        rpc_pool.check_health().await;
+        let status = rpc_pool.status();
+
+        assert!(!status[0].healthy);
+        assert_eq!(status[0].errors, 2);
+        assert_eq!(status[0].block_number, None);
+        assert_eq!(status[1].errors, 0);
+        assert_eq!(status[1].block_number, Some(16));
+        assert_eq!(status[1].block_lag, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_connect_fails_without_usable_endpoint() {
+        let unreachable = RpcEndpointConfig {
+            url: "http://127.0.0.1:1".to_string(),
+            ..endpoint(0, 1)
+        };
+        assert!(RpcPool::connect(NODE_CHAIN_ID, &[unreachable])
+            .await
+            .is_err());
+
+        // a node of another chain is not usable either
+        let other_chain = RpcEndpointConfig {
+            url: fixture_node().await,
+            ..endpoint(0, 1)
+        };
+        assert!(RpcPool::connect(NODE_CHAIN_ID + 1, &[other_chain])
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_health_check_connects_unavailable_endpoint() {
+        // the port of the first node is free until the node comes up
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind test node");
+        let address = listener
+            .local_addr()
+            .expect("Failed to read test node address");
+        drop(listener);
+
+        let rpc_pool = RpcPool::connect(
+            NODE_CHAIN_ID,
+            &[
+                RpcEndpointConfig {
+                    url: format!("http://{}", address),
+                    ..endpoint(0, 1)
+                },
+                RpcEndpointConfig {
+                    url: fixture_node().await,
+                    ..endpoint(1, 1)
+                },
+            ],
+        )
+        .await
+        .expect("Failed to create rpc pool");
+        assert!(!rpc_pool.status()[0].healthy);
+
+        serve_fixture_node(
+            tokio::net::TcpListener::bind(address)
+                .await
+                .expect("Failed to bind test node"),
+        );
+        rpc_pool.check_health().await;
+
+        let status = rpc_pool.status();
+        assert_eq!(status[0].block_number, Some(16));
+        assert_eq!(status[0].block_lag, Some(0));
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+
+use alloy::json_abi::{Event, Function};
+
+use crate::config::AppState;
+
+use super::{
+    db::signature::fetch_signatures,
+    structs::{
+        log::TransactionLog,
+        signature::{SignatureEntry, SignatureGuess, SignatureImportFormat, TextSignature},
+        transaction::ResultTransaction,
+    },
+};
+
+// parses a human readable signature and computes its selector,
+// the signature is normalized to its canonical form e.g. without param names
+pub fn parse_signature(entry: &SignatureEntry) -> Result<TextSignature, String> {
+    let signature = entry.signature.trim();
+
+    let (selector, canonical) = match entry.kind.trim() {
+        "function" => {
+            let function = Function::parse(signature).map_err(|e| e.to_string())?;
+            (function.selector().to_string(), function.signature())
+        }
+        "event" => {
+            let event = Event::parse(signature).map_err(|e| e.to_string())?;
+            (event.selector().to_string(), event.signature())
+        }
+        kind => return Err(format!("Unknown signature kind: {}", kind)),
+    };
+
+    Ok(TextSignature {
+        selector,
+        signature: canonical,
+        kind: entry.kind.trim().to_string(),
+    })
+}
+
+// reads the entries of an import file, lines which can not be read are returned as errors
+pub fn parse_signature_entries(
+    body: &str,
+    format: &SignatureImportFormat,
+) -> Result<Vec<Result<SignatureEntry, String>>, String> {
+    match format {
+        SignatureImportFormat::Json => serde_json::from_str::<Vec<SignatureEntry>>(body)
+            .map(|entries| entries.into_iter().map(Ok).collect())
+            .map_err(|e| format!("Invalid JSON: {}", e)),
+        SignatureImportFormat::Csv => Ok(body
+            .lines()
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty() && *line != "kind,signature")
+            // signatures contain commas, so only the first one separates the columns
+            .map(|line| match line.split_once(',') {
+                Some((kind, signature)) => Ok(SignatureEntry {
+                    kind: kind.to_string(),
+                    signature: signature.trim_matches('"').to_string(),
+                }),
+                None => Err(line.to_string()),
+            })
+            .collect()),
+    }
+}
+
+// picks the first imported signature as best guess, reports the rest as alternatives
+pub fn guess_signature(selector: &str, signatures: &[&TextSignature]) -> Option<SignatureGuess> {
+    let (best, others) = signatures.split_first()?;
+
+    Some(SignatureGuess {
+        selector: selector.to_string(),
+        signature: best.signature.clone(),
+        ambiguous: !others.is_empty(),
+        alternatives: others.iter().map(|s| s.signature.clone()).collect(),
+    })
+}
+
+fn input_selector(input: &str) -> Option<String> {
+    let hex = input.trim_start_matches("0x");
+    if hex.len() < 8 {
+        return None;
+    }
+
+    Some(format!("0x{}", hex.get(..8)?.to_lowercase()))
+}
+
+fn log_selector(log: &TransactionLog) -> Option<String> {
+    log.topics.first().map(|topic| topic.to_lowercase())
+}
+
+// fetches the known signatures of the selectors, grouped by selector
+async fn fetch_signatures_by_selector(
+    state: &AppState,
+    mut selectors: Vec<String>,
+) -> HashMap<String, Vec<TextSignature>> {
+    let mut by_selector: HashMap<String, Vec<TextSignature>> = HashMap::new();
+
+    selectors.sort();
+    selectors.dedup();
+    if selectors.is_empty() {
+        return by_selector;
+    }
+
+    match fetch_signatures(&state.db_connection, selectors).await {
+        Ok(signatures) => {
+            for signature in signatures {
+                by_selector
+                    .entry(signature.selector.clone())
+                    .or_default()
+                    .push(signature);
+            }
+        }
+        Err(error) => println!("Failed to fetch signatures from db: {}", error),
+    }
+
+    by_selector
+}
+
+fn guess_of_kind(
+    signatures: &HashMap<String, Vec<TextSignature>>,
+    selector: &str,
+    kind: &str,
+) -> Option<SignatureGuess> {
+    let matching: Vec<&TextSignature> = signatures
+        .get(selector)?
+        .iter()
+        .filter(|signature| signature.kind == kind)
+        .collect();
+
+    guess_signature(selector, &matching)
+}
+
+fn resolve_logs_with(
+    signatures: &HashMap<String, Vec<TextSignature>>,
+    logs: &mut [TransactionLog],
+) {
+    for log in logs.iter_mut() {
+        if let Some(selector) = log_selector(log) {
+            log.signature = guess_of_kind(signatures, &selector, "event");
+        }
+    }
+}
+
+// resolves the input selector and the topic0 of the attached logs of the trxs
+pub async fn resolve_transaction_signatures(
+    state: &AppState,
+    transactions: &mut [ResultTransaction],
+) {
+    let mut selectors: Vec<String> = vec![];
+    for trx in transactions.iter() {
+        selectors.extend(input_selector(&trx.input));
+        if let Some(logs) = &trx.logs {
+            selectors.extend(logs.iter().filter_map(log_selector));
+        }
+    }
+
+    let signatures = fetch_signatures_by_selector(state, selectors).await;
+    if signatures.is_empty() {
+        return;
+    }
+
+    for trx in transactions.iter_mut() {
+        if let Some(selector) = input_selector(&trx.input) {
+            trx.input_signature = guess_of_kind(&signatures, &selector, "function");
+        }
+        if let Some(logs) = trx.logs.as_mut() {
+            resolve_logs_with(&signatures, logs);
+        }
+    }
+}
+
+// resolves the topic0 of the logs
+pub async fn resolve_log_signatures(state: &AppState, logs: &mut [TransactionLog]) {
+    let selectors = logs.iter().filter_map(log_selector).collect();
+
+    let signatures = fetch_signatures_by_selector(state, selectors).await;
+    resolve_logs_with(&signatures, logs);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: &str, signature: &str) -> SignatureEntry {
+        SignatureEntry {
+            kind: kind.to_string(),
+            signature: signature.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_signature() {
+        let function = parse_signature(&entry("function", "transfer(address to, uint256 amount)"))
+            .expect("Failed to parse function signature");
+        assert_eq!(function.selector, "0xa9059cbb");
+        assert_eq!(function.signature, "transfer(address,uint256)");
+
+        let event = parse_signature(&entry("event", "Transfer(address,address,uint256)"))
+            .expect("Failed to parse event signature");
+        assert_eq!(
+            event.selector,
+            "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        );
+
+        assert!(parse_signature(&entry("function", "transfer(address")).is_err());
+        assert!(parse_signature(&entry("error", "Unauthorized()")).is_err());
+    }
+
+    #[test]
+    fn test_parse_csv_signature_entries() {
+        let body = "kind,signature\nfunction,transfer(address,uint256)\n\ninvalid line\n";
+
+        let entries = parse_signature_entries(body, &SignatureImportFormat::Csv)
+            .expect("Failed to parse csv");
+
+        assert_eq!(entries.len(), 2);
+        let first = entries[0].as_ref().expect("Failed to parse csv line");
+        assert_eq!(first.kind, "function");
+        assert_eq!(first.signature, "transfer(address,uint256)");
+        assert_eq!(entries[1], Err("invalid line".to_string()));
+    }
+
+    #[test]
+    fn test_guess_signature() {
+        let first = TextSignature {
+            selector: "0x00000000".to_string(),
+            signature: "first()".to_string(),
+            kind: "function".to_string(),
+        };
+        let second = TextSignature {
+            signature: "second()".to_string(),
+            ..first.clone()
+        };
+
+        let unique = guess_signature("0x00000000", &[&first]).expect("No guess");
+        assert!(!unique.ambiguous);
+        assert_eq!(unique.signature, "first()");
+
+        let ambiguous = guess_signature("0x00000000", &[&first, &second]).expect("No guess");
+        assert!(ambiguous.ambiguous);
+        assert_eq!(ambiguous.signature, "first()");
+        assert_eq!(ambiguous.alternatives, vec!["second()".to_string()]);
+
+        assert!(guess_signature("0x00000000", &[]).is_none());
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct ContractAbi {
+    pub address: String,  // the lowercase etherum address of the contract
+    pub abi: Json<Value>, // the JSON ABI of the contract
+    #[serde(rename = "uploadedBy")]
+    pub uploaded_by: String, // the name of the user who uploaded the ABI
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AbiPayload {
+    pub address: String,
+    pub abi: Value,
+}
+
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct DecodedParam {
+    pub name: String, // the name of the parameter in the ABI, empty when unnamed
+    #[serde(rename = "type")]
+    pub kind: String, // the solidity type of the parameter
+    pub value: Value, // the decoded value, numbers are returned as decimal strings
+}
+
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct DecodedCall {
+    pub function: String,  // the name of the called function
+    pub signature: String, // the function signature, e.g. transfer(address,uint256)
+    pub args: Vec<DecodedParam>,
+}
+
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct DecodedEvent {
+    pub event: String,     // the name of the emitted event
+    pub signature: String, // the event signature, e.g. Transfer(address,address,uint256)
+    pub args: Vec<DecodedParam>,
+}
@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use super::transaction::ResultTransaction;
+
+#[derive(Debug, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressDirection {
+    Sent,     // the address is the sender of the transaction
+    Received, // the address is the receiver of the transaction
+    Created,  // the address is the contract created by the transaction
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddressTransactionsQuery {
+    pub direction: Option<AddressDirection>, // all directions when missing
+    #[serde(default = "default_address_transactions_limit")]
+    pub limit: i64,
+    pub cursor: Option<String>, // the nextCursor of the previous page
+}
+
+fn default_address_transactions_limit() -> i64 {
+    50
+}
+
+#[derive(Serialize)]
+pub struct AddressTransactionsResponse {
+    pub transactions: Vec<ResultTransaction>,
+    #[serde(rename = "nextCursor", skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>, // passed as cursor to fetch the next page, missing on the last page
+}
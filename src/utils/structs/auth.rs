@@ -3,7 +3,7 @@ use reqwest::StatusCode;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    config::AppState,
+    config::{AppState, ADMIN_USERS},
     utils::{auth::decode_jwt, db::user::fetch_user},
 };
 
@@ -19,6 +19,10 @@ impl AuthUser {
     pub fn db_user(&self) -> &DbUser {
         &self.0 // Accessing the inner DbUser
     }
+
+    pub fn is_admin(&self) -> bool {
+        ADMIN_USERS.contains(&self.0.name)
+    }
 }
 #[async_trait]
 impl FromRequestParts<AppState> for AuthUser {
@@ -40,6 +44,31 @@ impl FromRequestParts<AppState> for AuthUser {
         }
     }
 }
+// an authenticated user listed in ADMIN_USERS
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AdminUser(DbUser);
+impl AdminUser {
+    pub fn db_user(&self) -> &DbUser {
+        &self.0
+    }
+}
+#[async_trait]
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<AdminUser, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+
+        if user.is_admin() {
+            Ok(AdminUser(user.0))
+        } else {
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+}
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JwtPayload {
     pub user: String,
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+use super::transaction::{FetchError, ResultTransaction, TransactionState};
+
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ResultBlock {
+    #[serde(rename = "blockHash")]
+    pub block_hash: String, // the hex encoded hash of the block
+    #[serde(rename = "blockNumber")]
+    pub block_number: i32, // the height of the block
+    #[serde(rename = "parentHash")]
+    pub parent_hash: String, // the hex encoded hash of the parent block
+    pub timestamp: i64, // unix timestamp (seconds) of the block
+    #[serde(rename = "baseFeePerGas")]
+    pub base_fee_per_gas: Option<String>, // the base fee per gas in wei, null before EIP-1559
+    #[serde(rename = "gasUsed")]
+    pub gas_used: i64, // the gas used by all transactions of the block
+    #[serde(rename = "gasLimit")]
+    pub gas_limit: i64, // the gas limit of the block
+    pub miner: String,  // the etherum address receiving the priority fees of the block
+    #[serde(rename = "transactionHashes")]
+    pub transaction_hashes: Vec<String>, // the hex encoded hashes of the transactions in block order
+    #[sqlx(try_from = "String")]
+    pub state: TransactionState, // either latest, safe or finalized
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlockQuery {
+    #[serde(default)]
+    pub expand: bool, // when true, the transactions of the block are returned
+}
+
+#[derive(Serialize)]
+pub struct BlockResponse {
+    #[serde(flatten)]
+    pub block: ResultBlock,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transactions: Option<Vec<ResultTransaction>>, // only present when requested with expand=true
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FetchError>,
+    #[serde(rename = "cacheOnly", skip_serializing_if = "std::ops::Not::not")]
+    pub cache_only: bool, // true while the node is unavailable and only cached trxs are served
+}
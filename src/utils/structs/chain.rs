@@ -0,0 +1,91 @@
+use std::{fmt, time::Duration};
+
+use alloy::transports::{RpcError, TransportErrorKind};
+use serde::Serialize;
+
+// the kind of a failed rpc call, each kind is retried with its own budget
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcErrorKind {
+    RateLimited, // the node answered 429 or a JSON-RPC limit error
+    Timeout,     // the call or the gateway in front of the node timed out
+    NodeError,   // the node failed with a 5xx or could not be reached
+    Rejected,    // the node rejected the call or answered garbage, never retried
+}
+impl fmt::Display for RpcErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            RpcErrorKind::RateLimited => "rate limited",
+            RpcErrorKind::Timeout => "timed out",
+            RpcErrorKind::NodeError => "node error",
+            RpcErrorKind::Rejected => "rejected",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+// the failure of a call to a single rpc endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcCallError {
+    pub kind: RpcErrorKind,
+    pub message: String,
+    pub retry_after: Option<Duration>, // the wait asked for by the Retry-After header
+}
+impl fmt::Display for RpcCallError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
+}
+impl std::error::Error for RpcCallError {}
+
+// the reason a trx could not be resolved from the db cache or the chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainError {
+    InvalidHash(String),
+    NotFound(String),      // the node does not know the trx hash
+    BlockNotFound(String), // the node does not know the block number, hash or tag
+    Rpc { kind: RpcErrorKind, message: String },
+    Database(String),
+    DeadlineExceeded, // the request deadline passed before the trx was resolved
+    CircuitOpen,      // the node is failing, trxs missing in the cache can not be fetched
+}
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChainError::InvalidHash(message) => write!(f, "{}", message),
+            ChainError::NotFound(transaction_hash) => {
+                write!(f, "No transaction found for trx_hash {}", transaction_hash)
+            }
+            ChainError::BlockNotFound(block_id) => write!(f, "No block found for {}", block_id),
+            ChainError::Rpc { kind, message } => write!(f, "Rpc call {}: {}", kind, message),
+            ChainError::Database(message) => write!(f, "Db query failed: {}", message),
+            ChainError::DeadlineExceeded => write!(f, "Timed out at the request deadline"),
+            ChainError::CircuitOpen => write!(
+                f,
+                "The node is unavailable, only cached transactions are served"
+            ),
+        }
+    }
+}
+impl From<RpcError<TransportErrorKind>> for ChainError {
+    fn from(error: RpcError<TransportErrorKind>) -> ChainError {
+        // the rpc pool reports the endpoint failures of the last attempt as RpcCallError
+        if let RpcError::Transport(TransportErrorKind::Custom(custom)) = &error {
+            if let Some(call_error) = custom.downcast_ref::<RpcCallError>() {
+                return ChainError::Rpc {
+                    kind: call_error.kind,
+                    message: call_error.message.clone(),
+                };
+            }
+        }
+
+        let kind = match &error {
+            RpcError::ErrorResp(_) => RpcErrorKind::NodeError,
+            _ => RpcErrorKind::Rejected,
+        };
+        ChainError::Rpc {
+            kind,
+            message: error.to_string(),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CircuitState {
+    Closed,   // calls go to the node
+    Open,     // calls are refused, trxs are served from the cache only
+    HalfOpen, // a single probe call decides whether the breaker closes again
+}
+impl CircuitState {
+    // the value of the state gauge in the metrics
+    pub fn as_metric(&self) -> u8 {
+        match self {
+            CircuitState::Closed => 0,
+            CircuitState::Open => 1,
+            CircuitState::HalfOpen => 2,
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CircuitBreakerStatus {
+    pub state: CircuitState,
+    #[serde(rename = "consecutiveFailures")]
+    pub consecutive_failures: u32,
+    #[serde(rename = "timesOpened")]
+    pub times_opened: u64,
+    #[serde(rename = "refusedCalls")]
+    pub refused_calls: u64, // node calls refused while the breaker was open
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+
+// the status of a trx as seen on one poll of its event stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionStatus {
+    Pending,
+    Included {
+        block_number: i32,
+        block_hash: String,
+        confirmations: u64,
+        finalized: bool,
+    },
+    Dropped,  // the node no longer knows the trx
+    Replaced, // another trx of the sender with the same nonce was included
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionEventKind {
+    Pending,
+    Included,
+    Confirmations, // the trx reached the requested number of confirmations
+    Finalized,
+    Reorged, // the block the trx was included in is no longer canonical
+    Dropped,
+    Replaced,
+}
+impl TransactionEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionEventKind::Pending => "pending",
+            TransactionEventKind::Included => "included",
+            TransactionEventKind::Confirmations => "confirmations",
+            TransactionEventKind::Finalized => "finalized",
+            TransactionEventKind::Reorged => "reorged",
+            TransactionEventKind::Dropped => "dropped",
+            TransactionEventKind::Replaced => "replaced",
+        }
+    }
+
+    // the stream is closed after these events
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            TransactionEventKind::Finalized
+                | TransactionEventKind::Dropped
+                | TransactionEventKind::Replaced
+        )
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TransactionEvent {
+    #[serde(skip)]
+    pub kind: TransactionEventKind, // sent as the name of the SSE event
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: String,
+    #[serde(rename = "blockNumber")]
+    pub block_number: Option<i32>, // the block of the trx, for reorged the block it left
+    #[serde(rename = "blockHash")]
+    pub block_hash: Option<String>,
+    pub confirmations: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransactionEventsQuery {
+    pub confirmations: Option<u64>, // defaults to WEBHOOK_DEFAULT_CONFIRMATIONS
+}
@@ -0,0 +1,42 @@
+use alloy::rpc::types::Log;
+use serde::{Deserialize, Serialize};
+
+use super::{abi::DecodedEvent, signature::SignatureGuess};
+
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct TransactionLog {
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: String, // the hex encoded hash of the transaction which emitted the log
+    #[serde(rename = "logIndex")]
+    pub log_index: i32, // the position of the log in the block
+    pub address: String, // the etherum address of the contract which emitted the log
+    pub topics: Vec<String>, // the hex encoded indexed topics of the log
+    pub data: String,    // the hex encoding of the non-indexed log data
+    pub removed: bool,   // true when the log was removed due to a chain reorganization
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<DecodedEvent>, // the decoded event, when the ABI of the contract is registered
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<SignatureGuess>, // the known event signature of topic0
+}
+
+impl TransactionLog {
+    pub fn from_rpc_log(transaction_hash: &str, log: &Log) -> TransactionLog {
+        TransactionLog {
+            transaction_hash: transaction_hash.to_owned(),
+            log_index: log.log_index.unwrap_or(0) as i32,
+            address: log.address().to_string(),
+            topics: log.topics().iter().map(|topic| topic.to_string()).collect(),
+            data: log.data().data.to_string(),
+            removed: log.removed,
+            decoded: None,
+            signature: None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct LogsResponse {
+    pub logs: Vec<TransactionLog>,
+}
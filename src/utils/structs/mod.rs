@@ -1,2 +1,16 @@
+pub mod abi;
+pub mod address;
 pub mod auth;
+pub mod block;
+pub mod chain;
+pub mod circuit_breaker;
+pub mod events;
+pub mod log;
+pub mod reorg;
+pub mod rpc_pool;
+pub mod signature;
+pub mod trace;
 pub mod transaction;
+pub mod transfer;
+pub mod watchlist;
+pub mod webhook;
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct CanonicalBlock {
+    #[serde(rename = "blockNumber")]
+    pub block_number: i32, // the height of the block
+    #[serde(rename = "blockHash")]
+    pub block_hash: String, // the hex encoded hash of the canonical block at this height
+}
+
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct ReorgEvent {
+    pub id: i32,
+    #[serde(rename = "blockNumber")]
+    pub block_number: i32, // the height at which the canonical block changed
+    #[serde(rename = "oldBlockHash")]
+    pub old_block_hash: String, // the hash of the block which was reorged out
+    #[serde(rename = "newBlockHash")]
+    pub new_block_hash: String, // the hash of the new canonical block
+    #[serde(rename = "invalidatedTransactions")]
+    pub invalidated_transactions: i32, // number of cached transactions removed from the db
+    #[serde(rename = "detectedAt")]
+    pub detected_at: i64, // unix timestamp (seconds) of the detection
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReorgEventsQuery {
+    #[serde(default = "default_reorg_events_limit")]
+    pub limit: i64,
+}
+
+fn default_reorg_events_limit() -> i64 {
+    20
+}
+
+#[derive(Serialize)]
+pub struct ReorgEventsResponse {
+    pub reorgs: Vec<ReorgEvent>,
+}
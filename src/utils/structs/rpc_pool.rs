@@ -0,0 +1,30 @@
+use serde::Serialize;
+
+use super::circuit_breaker::CircuitBreakerStatus;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct RpcEndpointStatus {
+    pub url: String, // the scheme and host of the endpoint, the path might contain an api key
+    pub priority: u32,
+    pub weight: u32,
+    pub healthy: bool, // unhealthy endpoints are only called when no healthy endpoint is left
+    #[serde(rename = "blockNumber")]
+    pub block_number: Option<u64>, // the head block at the last successful health check
+    #[serde(rename = "blockLag")]
+    pub block_lag: Option<u64>, // blocks behind the most advanced endpoint of the chain
+    #[serde(rename = "errorRate")]
+    pub error_rate: f64, // share of failed calls within the last RPC_ERROR_WINDOW calls
+    pub calls: u64,
+    pub errors: u64,
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    #[serde(rename = "lastCheckedAt")]
+    pub last_checked_at: Option<i64>, // unix seconds of the last health check
+}
+
+#[derive(Serialize)]
+pub struct RpcEndpointsResponse {
+    pub endpoints: Vec<RpcEndpointStatus>,
+    #[serde(rename = "circuitBreaker")]
+    pub circuit_breaker: CircuitBreakerStatus,
+}
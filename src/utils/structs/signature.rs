@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct TextSignature {
+    pub selector: String, // the hex encoded 4-byte function selector or 32-byte event topic0
+    pub signature: String, // the canonical signature, e.g. transfer(address,uint256)
+    pub kind: String,     // either function or event
+}
+
+#[derive(PartialEq, Eq, Debug, Deserialize, Clone)]
+pub struct SignatureEntry {
+    pub kind: String,      // either function or event
+    pub signature: String, // the human readable signature
+}
+
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct SignatureGuess {
+    pub selector: String,  // the hex encoded selector, which was looked up
+    pub signature: String, // the best guess, the first imported matching signature
+    pub ambiguous: bool,   // true when several signatures share the selector
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub alternatives: Vec<String>, // the other matching signatures, only present when ambiguous
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SignatureImportQuery {
+    #[serde(default)]
+    pub format: SignatureImportFormat,
+}
+
+#[derive(Debug, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureImportFormat {
+    #[default]
+    Json, // an array of {"kind": "function", "signature": "transfer(address,uint256)"}
+    Csv, // lines of kind,signature e.g. function,transfer(address,uint256)
+}
+
+#[derive(Serialize)]
+pub struct SignatureImportResponse {
+    pub imported: u64,        // number of newly saved signatures
+    pub duplicates: u64,      // number of valid signatures, which were already saved
+    pub invalid: Vec<String>, // the entries which could not be parsed
+}
@@ -0,0 +1,62 @@
+use alloy::primitives::{Address, Bytes, U256, U64};
+use serde::{Deserialize, Serialize};
+
+use super::chain::ChainError;
+
+// a call frame as returned by the callTracer of debug_traceTransaction
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CallFrame {
+    #[serde(rename = "type")]
+    pub call_type: String,
+    pub from: Address,
+    pub to: Option<Address>,
+    pub value: Option<U256>,
+    pub gas: U64,
+    pub gas_used: U64,
+    pub input: Bytes,
+    pub output: Option<Bytes>,
+    pub error: Option<String>,
+    pub revert_reason: Option<String>,
+    #[serde(default)]
+    pub calls: Vec<CallFrame>,
+}
+
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct TraceCall {
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: String, // the hex encoded hash of the traced transaction
+    #[serde(rename = "traceIndex")]
+    pub trace_index: i32, // the position of the call in the depth first order of the call tree
+    #[serde(rename = "parentIndex")]
+    pub parent_index: Option<i32>, // the traceIndex of the calling frame, null for the top level call
+    pub depth: i32, // the depth of the call in the call tree, 0 for the top level call
+    #[serde(rename = "callType")]
+    pub call_type: String, // CALL, STATICCALL, DELEGATECALL, CALLCODE, CREATE, CREATE2 or SELFDESTRUCT
+    pub from: String,       // the etherum address of the caller
+    pub to: Option<String>, // the etherum address of the callee or created contract
+    pub value: String,      // the transferred value in wei
+    pub gas: i64,           // the gas provided to the call
+    #[serde(rename = "gasUsed")]
+    pub gas_used: i64, // the gas used by the call
+    pub input: String,      // the hex encoded calldata or init code
+    pub output: Option<String>, // the hex encoded return data
+    pub error: Option<String>, // the error when the call failed, e.g. execution reverted
+    #[serde(rename = "revertReason")]
+    pub revert_reason: Option<String>, // the decoded revert reason, when provided by the node
+}
+
+// the reason the trace of a trx could not be returned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceError {
+    Unsupported(String), // the node refused the trace, e.g. it has no debug namespace
+    Failed(ChainError),  // the node could not be called or the db failed
+}
+
+#[derive(Serialize)]
+pub struct TraceResponse {
+    pub available: bool, // false when the trace could not be captured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>, // why the trace is not available
+    pub calls: Vec<TraceCall>,
+}
@@ -1,5 +1,19 @@
-use crate::utils::misc::comma_separated_to_vec;
+use crate::{
+    config::FINALITY_TAG,
+    utils::{
+        misc::{comma_separated_to_vec, parse_date_to_unix, unix_to_iso8601},
+        structs::{
+            abi::DecodedCall,
+            chain::{ChainError, RpcErrorKind},
+            log::TransactionLog,
+            signature::SignatureGuess,
+            transfer::TokenTransferSummary,
+        },
+    },
+};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
 
 #[derive(Debug, Deserialize)]
 pub struct TransactionHashesQuery {
@@ -9,17 +23,64 @@ pub struct TransactionHashesQuery {
         deserialize_with = "comma_separated_to_vec"
     )]
     pub transaction_hashes: Vec<String>,
+    #[serde(rename = "includeLogs", default)]
+    pub include_logs: bool,
 }
-#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize)]
+#[derive(PartialEq, Eq, Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionState {
+    Pending, // the transaction is not included in a block yet, it is never saved in the db
+    #[default]
+    Latest, // the transaction is included in a block above the safe block
+    Safe,    // the transaction is included at or below the safe block
+    Finalized, // the transaction is included at or below the finalized block
+}
+impl TransactionState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TransactionState::Pending => "pending",
+            TransactionState::Latest => "latest",
+            TransactionState::Safe => "safe",
+            TransactionState::Finalized => "finalized",
+        }
+    }
+
+    // the saved states treated as immutable, depending on the FINALITY_TAG env
+    pub fn immutable_states() -> Vec<&'static str> {
+        match FINALITY_TAG.as_str() {
+            "safe" => vec!["safe", "finalized"],
+            _ => vec!["finalized"],
+        }
+    }
+}
+impl TryFrom<String> for TransactionState {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "pending" => Ok(TransactionState::Pending),
+            "latest" => Ok(TransactionState::Latest),
+            "safe" => Ok(TransactionState::Safe),
+            "finalized" => Ok(TransactionState::Finalized),
+            _ => Err(format!("Unknown transaction state {}", value)),
+        }
+    }
+}
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone, Default)]
 pub struct ResultTransaction {
     #[serde(rename = "transactionHash")]
     pub transaction_hash: String, // the hex encoded transaction hash of the transaction
     #[serde(rename = "transactionStatus")]
-    pub transaction_status: i16, // the status of the transaction either 1 (success) or 0 (failure)
+    pub transaction_status: Option<i16>, // the status of the transaction either 1 (success) or 0 (failure), null while pending
     #[serde(rename = "blockHash")]
-    pub block_hash: String, // the hex encoding of the hash of the block the transaction was included in
+    pub block_hash: Option<String>, // the hex encoding of the hash of the block the transaction was included in, null while pending
     #[serde(rename = "blockNumber")]
-    pub block_number: i32, // the number of the block the transaction was included in
+    pub block_number: Option<i32>, // the number of the block the transaction was included in, null while pending
+    #[serde(rename = "blockTimestamp")]
+    pub block_timestamp: Option<i64>, // unix timestamp (seconds) of the block the transaction was included in, null while pending
+    #[sqlx(skip)]
+    #[serde(rename = "blockTime")]
+    pub block_time: Option<String>, // the block timestamp as ISO-8601 in UTC, null while pending
     pub from: String,       // the etherum address of the transaction sender
     pub to: Option<String>, // the etherum address of the transaction receiver or null when its a contract creation transaction.
     #[serde(rename = "contractAddress")]
@@ -28,8 +89,105 @@ pub struct ResultTransaction {
     pub logs_count: i32, // number of log objects, which this transaction generated.
     pub input: String, // the hex encoding of the data send along with the transaction.
     pub value: String, // the value transferred in wei
+    #[serde(rename = "transactionType")]
+    pub transaction_type: Option<i16>, // the EIP-2718 type, 0 (legacy), 1 (EIP-2930), 2 (EIP-1559), 3 (EIP-4844) or 4 (EIP-7702)
+    pub nonce: Option<i64>, // the number of transactions sent by the sender before this one
+    #[serde(rename = "gasLimit")]
+    pub gas_limit: Option<i64>, // the max amount of gas the transaction may use
+    #[serde(rename = "gasUsed")]
+    pub gas_used: Option<i64>, // the amount of gas used by the transaction, null while pending
+    #[serde(rename = "effectiveGasPrice")]
+    pub effective_gas_price: Option<String>, // the price per gas paid in wei, null while pending
+    #[serde(rename = "maxFeePerGas")]
+    pub max_fee_per_gas: Option<String>, // the max total fee per gas in wei, null for legacy and EIP-2930 transactions
+    #[serde(rename = "maxPriorityFeePerGas")]
+    pub max_priority_fee_per_gas: Option<String>, // the max priority fee per gas in wei, null for legacy and EIP-2930 transactions
+    #[serde(rename = "accessList")]
+    pub access_list: Option<Json<Value>>, // the EIP-2930 access list, null for legacy transactions
+    #[serde(rename = "blobVersionedHashes")]
+    pub blob_versioned_hashes: Option<Vec<String>>, // the versioned hashes of the blobs, null for non EIP-4844 transactions
+    #[serde(rename = "maxFeePerBlobGas")]
+    pub max_fee_per_blob_gas: Option<String>, // the max fee per blob gas in wei, null for non EIP-4844 transactions
+    #[serde(rename = "authorizationList")]
+    pub authorization_list: Option<Json<Value>>, // the signed EIP-7702 authorizations, null for non EIP-7702 transactions
+    #[serde(rename = "revertReason")]
+    pub revert_reason: Option<String>, // the decoded revert reason of failed transactions
+    #[serde(rename = "revertData")]
+    pub revert_data: Option<String>, // the hex encoded revert data of failed transactions, null until resolved
+    #[sqlx(try_from = "String")]
+    pub state: TransactionState, // either pending, latest, safe or finalized
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logs: Option<Vec<TransactionLog>>, // the log objects of the transaction, only present when requested with includeLogs=true
+    #[sqlx(skip)]
+    #[serde(rename = "decodedInput", skip_serializing_if = "Option::is_none")]
+    pub decoded_input: Option<DecodedCall>, // the decoded calldata, when the ABI of the called contract is registered
+    #[sqlx(skip)]
+    #[serde(rename = "inputSignature", skip_serializing_if = "Option::is_none")]
+    pub input_signature: Option<SignatureGuess>, // the known function signature of the first 4 bytes of input
+    #[sqlx(skip)]
+    #[serde(rename = "tokenTransfers", skip_serializing_if = "Option::is_none")]
+    pub token_transfers: Option<Vec<TokenTransferSummary>>, // the number of token transfers per token
+}
+impl ResultTransaction {
+    // sets the ISO-8601 block time derived from the block timestamp
+    pub fn with_block_time(mut self) -> ResultTransaction {
+        self.block_time = self.block_timestamp.and_then(unix_to_iso8601);
+        self
+    }
+}
+#[derive(Serialize)]
+pub struct FetchError {
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: String, // the requested transaction hash
+    pub error: String, // the reason the transaction could not be returned
+    #[serde(rename = "timedOut")]
+    pub timed_out: bool, // true when the request deadline passed before it was resolved
+}
+impl FetchError {
+    pub fn new(transaction_hash: String, error: &ChainError) -> FetchError {
+        FetchError {
+            transaction_hash,
+            error: error.to_string(),
+            timed_out: matches!(
+                error,
+                ChainError::DeadlineExceeded
+                    | ChainError::Rpc {
+                        kind: RpcErrorKind::Timeout,
+                        ..
+                    }
+            ),
+        }
+    }
 }
 #[derive(Serialize)]
 pub struct FetchResponse {
     pub transactions: Vec<ResultTransaction>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FetchError>,
+    #[serde(rename = "cacheOnly", skip_serializing_if = "std::ops::Not::not")]
+    pub cache_only: bool, // true while the node is unavailable and only cached trxs are served
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DateRangeQuery {
+    pub from: Option<String>, // unix seconds, ISO-8601 date time or YYYY-MM-DD, inclusive
+    pub to: Option<String>,   // unix seconds, ISO-8601 date time or YYYY-MM-DD, inclusive
+}
+impl DateRangeQuery {
+    // the range as unix seconds, a "to" date without time includes the whole day
+    pub fn unix_range(&self) -> Result<(Option<i64>, Option<i64>), String> {
+        let from = self
+            .from
+            .as_ref()
+            .map(|from| parse_date_to_unix(from, false))
+            .transpose()?;
+        let to = self
+            .to
+            .as_ref()
+            .map(|to| parse_date_to_unix(to, true))
+            .transpose()?;
+
+        Ok((from, to))
+    }
 }
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct TokenTransfer {
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: String, // the hex encoded hash of the transaction
+    #[serde(rename = "logIndex")]
+    pub log_index: i32, // the position of the emitting log in the block
+    #[serde(rename = "batchIndex")]
+    pub batch_index: i32, // the position of the token in an ERC-1155 TransferBatch, otherwise 0
+    pub standard: String,       // either erc20, erc721 or erc1155
+    pub token: String,          // the etherum address of the token contract
+    pub from: String,           // the etherum address of the sender, the zero address for mints
+    pub to: String,             // the etherum address of the receiver, the zero address for burns
+    pub amount: Option<String>, // the transferred amount, null for ERC-721 transfers
+    #[serde(rename = "tokenId")]
+    pub token_id: Option<String>, // the transferred token id, null for ERC-20 transfers
+}
+
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct TokenTransferSummary {
+    #[serde(skip)]
+    pub transaction_hash: String,
+    pub token: String,    // the etherum address of the token contract
+    pub standard: String, // either erc20, erc721 or erc1155
+    pub transfers: i64,   // number of transfers of the token in the transaction
+}
+
+#[derive(Serialize)]
+pub struct TransfersResponse {
+    pub transfers: Vec<TokenTransfer>,
+}
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct WatchedAddress {
+    pub address: String,       // the lowercase watched address
+    pub label: Option<String>, // a free text note of the user
+    #[serde(rename = "createdAt")]
+    pub created_at: i64, // unix timestamp (seconds) of when the address was watched
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WatchlistPayload {
+    pub address: String,
+    pub label: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct WatchlistResponse {
+    pub addresses: Vec<WatchedAddress>,
+}
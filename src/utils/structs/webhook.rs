@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct Webhook {
+    pub id: i32,
+    pub url: String, // the url the signed events are posted to
+    #[serde(skip_serializing)]
+    pub secret: String, // the HMAC key of the signatures, only returned on registration
+    #[serde(rename = "createdAt")]
+    pub created_at: i64, // unix timestamp (seconds) of the registration
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookPayload {
+    pub url: String,
+}
+
+#[derive(Serialize)]
+pub struct RegisteredWebhook {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+#[derive(Serialize)]
+pub struct WebhooksResponse {
+    pub webhooks: Vec<Webhook>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    TransactionMined,     // a tracked trx was included in a block
+    TransactionConfirmed, // a tracked trx reached its number of confirmations
+    TransactionReorged,   // the block of a tracked trx was reorged out
+    AddressSeen,          // a trx of a watched address was included in a new block
+}
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::TransactionMined => "transaction.mined",
+            WebhookEvent::TransactionConfirmed => "transaction.confirmed",
+            WebhookEvent::TransactionReorged => "transaction.reorged",
+            WebhookEvent::AddressSeen => "address.seen",
+        }
+    }
+}
+
+#[derive(sqlx::FromRow, PartialEq, Eq, Debug, Deserialize, Serialize, Clone)]
+pub struct TrackedTransaction {
+    #[serde(skip_serializing)]
+    pub user_name: String,
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: String,
+    pub confirmations: i32, // the confirmations after which transaction.confirmed is sent
+    #[serde(rename = "blockNumber")]
+    pub block_number: Option<i32>, // the block the trx was last seen in, null while pending
+    #[serde(rename = "blockHash")]
+    pub block_hash: Option<String>,
+    pub confirmed: bool, // confirmed trxs are no longer checked
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrackPayload {
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: String,
+    pub confirmations: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct TrackedTransactionsResponse {
+    pub transactions: Vec<TrackedTransaction>,
+}
+
+// an outbox entry, retried until delivered or WEBHOOK_MAX_ATTEMPTS is reached
+#[derive(sqlx::FromRow, PartialEq, Debug, Serialize, Clone)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    #[serde(rename = "webhookId")]
+    pub webhook_id: i32,
+    pub event: String,
+    pub payload: Json<Value>, // the data of the event
+    pub status: String,       // pending, delivered or failed
+    pub attempts: i32,
+    #[serde(rename = "lastStatusCode")]
+    pub last_status_code: Option<i32>, // the http status of the last attempt
+    #[serde(rename = "lastError")]
+    pub last_error: Option<String>,
+    #[serde(rename = "nextAttemptAt")]
+    pub next_attempt_at: Option<i64>, // unix timestamp (seconds) of the next retry, null once done
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    #[serde(rename = "deliveredAt")]
+    pub delivered_at: Option<i64>,
+}
+
+// a pending delivery with the webhook it is sent to
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct DueDelivery {
+    pub id: i64,
+    pub chain_id: i64,
+    pub event: String,
+    pub payload: Json<Value>,
+    pub attempts: i32,
+    pub created_at: i64,
+    pub url: String,
+    pub secret: String,
+}
+
+// the JSON body posted to the webhook url
+#[derive(Serialize)]
+pub struct WebhookBody {
+    pub id: i64, // the delivery id, the same on every retry
+    pub event: String,
+    #[serde(rename = "chainId")]
+    pub chain_id: i64,
+    #[serde(rename = "createdAt")]
+    pub created_at: i64,
+    pub data: Value,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WebhookDeliveriesQuery {
+    #[serde(default = "default_webhook_deliveries_limit")]
+    pub limit: i64,
+}
+
+fn default_webhook_deliveries_limit() -> i64 {
+    20
+}
+
+#[derive(Serialize)]
+pub struct WebhookDeliveriesResponse {
+    pub deliveries: Vec<WebhookDelivery>,
+}
@@ -0,0 +1,197 @@
+use alloy::{providers::Provider, transports::RpcError};
+use serde_json::json;
+
+use crate::config::AppState;
+
+use super::{
+    chain::is_transport_failure,
+    db::trace::{fetch_transaction_trace, save_trace_call},
+    misc::parse_b256_from_str,
+    structs::{
+        chain::ChainError,
+        trace::{CallFrame, TraceCall, TraceError},
+    },
+};
+
+// flattens the call tree in depth first order, linking each call to its parent
+pub fn flatten_call_frame(transaction_hash: &str, root: &CallFrame) -> Vec<TraceCall> {
+    let mut calls: Vec<TraceCall> = vec![];
+    // frames waiting to be visited, together with their parent index and depth
+    let mut stack: Vec<(&CallFrame, Option<i32>, i32)> = vec![(root, None, 0)];
+
+    while let Some((frame, parent_index, depth)) = stack.pop() {
+        let trace_index = calls.len() as i32;
+
+        calls.push(TraceCall {
+            transaction_hash: transaction_hash.to_owned(),
+            trace_index,
+            parent_index,
+            depth,
+            call_type: frame.call_type.to_uppercase(),
+            from: frame.from.to_string(),
+            to: frame.to.map(|to| to.to_string()),
+            value: frame.value.unwrap_or_default().to_string(),
+            gas: frame.gas.to::<u64>() as i64,
+            gas_used: frame.gas_used.to::<u64>() as i64,
+            input: frame.input.to_string(),
+            output: frame.output.as_ref().map(|output| output.to_string()),
+            error: frame.error.clone(),
+            revert_reason: frame.revert_reason.clone(),
+        });
+
+        // pushed in reverse, so the first sub call is visited next
+        for call in frame.calls.iter().rev() {
+            stack.push((call, Some(trace_index), depth + 1));
+        }
+    }
+
+    calls
+}
+
+// fetches the call tree with the callTracer of the node's debug namespace, only the error
+// answer of the node is a reason to show, failed calls might contain the rpc url
+async fn fetch_trace_from_node(
+    transaction_hash: &str,
+    state: &AppState,
+) -> Result<CallFrame, TraceError> {
+    let b256_transaction_hash = parse_b256_from_str(transaction_hash)
+        .map_err(|e| TraceError::Failed(ChainError::InvalidHash(e)))?;
+
+    // the trace counts towards the circuit breaker, it is not requested while the breaker is open
+    let permit = state
+        .circuit_breaker
+        .try_acquire()
+        .ok_or(TraceError::Failed(ChainError::CircuitOpen))?;
+    let result = state
+        .eth_client
+        .client()
+        .request::<_, CallFrame>(
+            "debug_traceTransaction",
+            (b256_transaction_hash, json!({ "tracer": "callTracer" })),
+        )
+        .await;
+    permit.record(!is_transport_failure(&result));
+
+    result.map_err(|e| match e {
+        RpcError::ErrorResp(payload) => TraceError::Unsupported(format!(
+            "Node does not provide a call trace: {}",
+            payload.message
+        )),
+        e => TraceError::Failed(e.into()),
+    })
+}
+
+// returns the saved trace of the trx, tracing it on the node only once,
+// the hash is expected to be normalized like the saved trxs
+pub async fn trace_transaction(
+    transaction_hash: &String,
+    state: &AppState,
+) -> Result<Vec<TraceCall>, TraceError> {
+    let db_error = |action: &str, e: sqlx::Error| {
+        TraceError::Failed(ChainError::Database(format!(
+            "Failed to {} trace: {}",
+            action, e
+        )))
+    };
+
+    let saved_calls =
+        fetch_transaction_trace(&state.db_connection, state.chain_id(), transaction_hash)
+            .await
+            .map_err(|e| db_error("fetch", e))?;
+    if !saved_calls.is_empty() {
+        return Ok(saved_calls);
+    }
+
+    let root = fetch_trace_from_node(transaction_hash, state).await?;
+    let calls = flatten_call_frame(transaction_hash, &root);
+
+    // the calls are saved all at once, a partly saved trace would be served as the full one
+    let mut db_trx = state
+        .db_connection
+        .begin()
+        .await
+        .map_err(|e| db_error("save", e))?;
+    for call in calls.iter() {
+        save_trace_call(&mut *db_trx, state.chain_id(), call)
+            .await
+            .map_err(|e| db_error("save", e))?;
+    }
+    db_trx.commit().await.map_err(|e| db_error("save", e))?;
+
+    Ok(calls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_call_frame() {
+        let root: CallFrame = serde_json::from_value(json!({
+            "type": "CALL",
+            "from": "0x5520a8a1723fdc8a8e64da1f348cc1991c13c1c3",
+            "to": "0xea58fca6849d79ead1f26608855c2d6407d54ce2",
+            "value": "0xde0b6b3a7640000",
+            "gas": "0x7530",
+            "gasUsed": "0x5208",
+            "input": "0x",
+            "calls": [
+                {
+                    "type": "DELEGATECALL",
+                    "from": "0xea58fca6849d79ead1f26608855c2d6407d54ce2",
+                    "to": "0x5520a8a1723fdc8a8e64da1f348cc1991c13c1c3",
+                    "gas": "0x100",
+                    "gasUsed": "0x10",
+                    "input": "0x12345678",
+                    "calls": [
+                        {
+                            "type": "CREATE",
+                            "from": "0x5520a8a1723fdc8a8e64da1f348cc1991c13c1c3",
+                            "gas": "0x10",
+                            "gasUsed": "0x10",
+                            "input": "0x60",
+                            "error": "out of gas"
+                        }
+                    ]
+                },
+                {
+                    "type": "STATICCALL",
+                    "from": "0xea58fca6849d79ead1f26608855c2d6407d54ce2",
+                    "to": "0x5520a8a1723fdc8a8e64da1f348cc1991c13c1c3",
+                    "gas": "0x100",
+                    "gasUsed": "0x10",
+                    "input": "0x",
+                    "output": "0x01"
+                }
+            ]
+        }))
+        .expect("Invalid test call frame");
+
+        let calls = flatten_call_frame("hash1", &root);
+
+        assert_eq!(
+            calls
+                .iter()
+                .map(|call| (
+                    call.trace_index,
+                    call.parent_index,
+                    call.depth,
+                    call.call_type.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, None, 0, "CALL"),
+                (1, Some(0), 1, "DELEGATECALL"),
+                (2, Some(1), 2, "CREATE"),
+                (3, Some(0), 1, "STATICCALL"),
+            ]
+        );
+        assert_eq!(calls[0].value, "1000000000000000000");
+        assert_eq!(calls[0].gas, 30000);
+        assert_eq!(calls[0].gas_used, 21000);
+        assert_eq!(calls[1].value, "0");
+        assert_eq!(calls[2].to, None);
+        assert_eq!(calls[2].error, Some("out of gas".to_string()));
+        assert_eq!(calls[3].output, Some("0x01".to_string()));
+    }
+}
@@ -0,0 +1,249 @@
+use std::{collections::HashMap, time::Duration};
+
+use futures::{stream, Stream, StreamExt};
+use tokio::time::Instant;
+
+use crate::config::{AppState, FETCH_CONCURRENCY, REQUEST_DEADLINE_MS, RPC_BATCH_SIZE};
+
+use super::{
+    abi::decode_transactions,
+    chain::{fetch_batch_from_chain, fetch_from_chain, fill_missing_block_timestamps},
+    db::{
+        log::fetch_transaction_logs,
+        transaction::{check_transaction_in_cache, check_transaction_in_db},
+        transfer::fetch_transfer_summaries,
+    },
+    revert::resolve_revert_reasons,
+    signature::resolve_transaction_signatures,
+    structs::{chain::ChainError, transaction::ResultTransaction},
+};
+
+// the instant until which a request started now may wait on the node
+pub fn request_deadline() -> Instant {
+    Instant::now() + Duration::from_millis(*REQUEST_DEADLINE_MS)
+}
+
+// fetches the trx from chain until the deadline, the fetch runs as a task like the batches,
+// so a fetch cut off by the deadline still completes and saves the trx
+pub async fn fetch_until(
+    state: &AppState,
+    transaction_hash: &str,
+    deadline: Instant,
+) -> Result<ResultTransaction, ChainError> {
+    let fetching = tokio::spawn({
+        let state = state.clone();
+        let transaction_hash = transaction_hash.to_owned();
+        async move { fetch_from_chain(&transaction_hash, &state).await }
+    });
+
+    match tokio::time::timeout_at(deadline, fetching).await {
+        Ok(Ok(trx)) => trx,
+        Ok(Err(error)) => {
+            println!("Fetch task failed, error:{}", error);
+            Err(ChainError::DeadlineExceeded)
+        }
+        Err(_) => {
+            println!("Request deadline reached, {} not fetched", transaction_hash);
+            Err(ChainError::DeadlineExceeded)
+        }
+    }
+}
+
+// collects the items the stream yields before the deadline
+async fn collect_until<S: Stream + Unpin>(deadline: Instant, mut stream: S) -> Vec<S::Item> {
+    let mut items = vec![];
+    loop {
+        match tokio::time::timeout_at(deadline, stream.next()).await {
+            Ok(Some(item)) => items.push(item),
+            Ok(None) => break,
+            Err(_) => {
+                println!(
+                    "Request deadline reached, {} results collected",
+                    items.len()
+                );
+                break;
+            }
+        }
+    }
+
+    items
+}
+
+// resolves the trx hashes from the db cache, fetching the missing ones from chain until the
+// deadline, the results are in the same order as the passed hashes
+pub async fn resolve_transactions(
+    state: &AppState,
+    query_list: Vec<String>,
+    deadline: Instant,
+) -> Vec<(String, Result<ResultTransaction, ChainError>)> {
+    // look the hashes up in the db concurrently, keeping the requested order
+    let cached: Vec<Result<Option<ResultTransaction>, sqlx::Error>> = stream::iter(
+        query_list.clone(),
+    )
+    .map(|transaction_hash| {
+        let db_connection = &state.db_connection;
+        let chain_id = state.chain_id();
+        async move { check_transaction_in_db(db_connection, chain_id, &transaction_hash).await }
+    })
+    .buffered(*FETCH_CONCURRENCY)
+    .collect()
+    .await;
+
+    // the hashes missing in the db are fetched from chain in JSON-RPC batches
+    let mut missing: Vec<String> = vec![];
+    for (transaction_hash, cached_trx) in query_list.iter().zip(cached.iter()) {
+        if let Ok(None) = cached_trx {
+            if !missing.contains(transaction_hash) {
+                missing.push(transaction_hash.clone());
+            }
+        }
+    }
+
+    let batches: Vec<Vec<String>> = missing
+        .chunks(*RPC_BATCH_SIZE)
+        .map(|chunk| chunk.to_vec())
+        .collect();
+    // the batches run as tasks, so a batch cut off by the deadline still completes
+    // and saves its trxs, instead of being dropped halfway through saving them
+    let fetching = stream::iter(batches)
+        .map(|chunk| {
+            let state = state.clone();
+            tokio::spawn(async move {
+                let chunk_result = fetch_batch_from_chain(&chunk, &state).await;
+                chunk.into_iter().zip(chunk_result).collect::<Vec<_>>()
+            })
+        })
+        .buffer_unordered(*FETCH_CONCURRENCY);
+
+    let mut fetched: HashMap<String, Result<ResultTransaction, ChainError>> = HashMap::new();
+    for chunk_result in collect_until(deadline, fetching).await {
+        match chunk_result {
+            Ok(chunk_result) => fetched.extend(chunk_result),
+            Err(error) => println!("Batch fetch task failed, error:{}", error),
+        }
+    }
+
+    let mut result: Vec<(String, Result<ResultTransaction, ChainError>)> = vec![];
+
+    for (transaction_hash, cached_trx) in query_list.into_iter().zip(cached) {
+        let trx = match cached_trx {
+            Ok(Some(res)) => {
+                println!("{} fetched from db", transaction_hash);
+                Ok(res)
+            }
+            Ok(None) => match fetched.get(&transaction_hash) {
+                Some(Ok(res)) => {
+                    println!("{} fetched and saved in db", transaction_hash);
+                    Ok(res.clone())
+                }
+                // while the node is unavailable an expired cached trx beats no trx
+                Some(Err(ChainError::CircuitOpen)) => {
+                    match check_transaction_in_cache(
+                        &state.db_connection,
+                        state.chain_id(),
+                        &transaction_hash,
+                        true,
+                    )
+                    .await
+                    {
+                        Ok(Some(res)) => {
+                            println!("{} fetched from stale cache", transaction_hash);
+                            Ok(res)
+                        }
+                        _ => Err(ChainError::CircuitOpen),
+                    }
+                }
+                Some(Err(error)) => Err(error.clone()),
+                None => Err(ChainError::DeadlineExceeded),
+            },
+            Err(error) => Err(ChainError::Database(error.to_string())),
+        };
+
+        result.push((transaction_hash, trx));
+    }
+
+    result
+}
+
+// attaches the logs when requested and the derived data of the trxs
+pub async fn enrich_transactions(
+    state: &AppState,
+    transactions: &mut [ResultTransaction],
+    include_logs: bool,
+    deadline: Instant,
+) {
+    // cached trxs whose block header was not available when they were saved
+    if tokio::time::timeout_at(deadline, fill_missing_block_timestamps(state, transactions))
+        .await
+        .is_err()
+    {
+        println!("Request deadline reached while resolving block timestamps");
+    }
+
+    // attach the saved logs if requested
+    if include_logs {
+        for trx in transactions.iter_mut() {
+            match fetch_transaction_logs(
+                &state.db_connection,
+                state.chain_id(),
+                &trx.transaction_hash,
+            )
+            .await
+            {
+                Ok(logs) => trx.logs = Some(logs),
+                Err(error) => println!("Failed to fetch logs from db: {}", error),
+            }
+        }
+    }
+
+    // resolve why failed trxs reverted, the reasons left at the deadline are skipped
+    if tokio::time::timeout_at(deadline, resolve_revert_reasons(state, transactions))
+        .await
+        .is_err()
+    {
+        println!("Request deadline reached while resolving revert reasons");
+    }
+
+    // attach the token transfer summary of each trx
+    let trx_hashes: Vec<String> = transactions
+        .iter()
+        .map(|trx| trx.transaction_hash.clone())
+        .collect();
+    match fetch_transfer_summaries(&state.db_connection, state.chain_id(), trx_hashes).await {
+        Ok(summaries) => {
+            for trx in transactions.iter_mut() {
+                trx.token_transfers = Some(
+                    summaries
+                        .iter()
+                        .filter(|summary| summary.transaction_hash == trx.transaction_hash)
+                        .cloned()
+                        .collect(),
+                );
+            }
+        }
+        Err(error) => println!("Failed to fetch token transfers from db: {}", error),
+    }
+
+    // decode the calldata and logs of contracts with registered ABIs
+    decode_transactions(state, transactions).await;
+    resolve_transaction_signatures(state, transactions).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_collect_until_deadline() {
+        let delayed = stream::iter([30, 10, 500, 20])
+            .map(|millis| async move {
+                tokio::time::sleep(Duration::from_millis(millis)).await;
+                millis
+            })
+            .buffer_unordered(4);
+
+        let items = collect_until(Instant::now() + Duration::from_millis(100), delayed).await;
+
+        assert_eq!(items, vec![10, 20, 30]);
+    }
+}
@@ -0,0 +1,232 @@
+use alloy::{
+    primitives::{Address, B256, U256},
+    sol_types::SolValue,
+};
+
+use super::{
+    misc::parse_b256_from_str,
+    structs::{log::TransactionLog, transfer::TokenTransfer},
+};
+
+// Transfer(address,address,uint256), shared by ERC-20 and ERC-721
+const TRANSFER_TOPIC: &str = "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+// TransferSingle(address,address,address,uint256,uint256)
+const TRANSFER_SINGLE_TOPIC: &str =
+    "0xc3d58168c5ae7397731d063d5bbf3d657854427343f4c083240f7aacaa2d0f62";
+// TransferBatch(address,address,address,uint256[],uint256[])
+const TRANSFER_BATCH_TOPIC: &str =
+    "0x4a39dc06d4c0dbc64b70af90fd698a233a518aa5d07e595d983b8c0526c8f7fb";
+
+fn topic_to_address(topic: &B256) -> String {
+    Address::from_word(*topic).to_string()
+}
+
+fn transfers_of_log(log: &TransactionLog) -> Option<Vec<TokenTransfer>> {
+    let topics: Vec<B256> = log
+        .topics
+        .iter()
+        .map(|topic| parse_b256_from_str(topic))
+        .collect::<Result<_, _>>()
+        .ok()?;
+    let data = hex::decode(log.data.trim_start_matches("0x")).ok()?;
+
+    let transfer = |standard: &str,
+                    from: &B256,
+                    to: &B256,
+                    batch_index: i32,
+                    amount: Option<U256>,
+                    token_id: Option<U256>| TokenTransfer {
+        transaction_hash: log.transaction_hash.clone(),
+        log_index: log.log_index,
+        batch_index,
+        standard: standard.to_string(),
+        token: log.address.clone(),
+        from: topic_to_address(from),
+        to: topic_to_address(to),
+        amount: amount.map(|amount| amount.to_string()),
+        token_id: token_id.map(|token_id| token_id.to_string()),
+    };
+
+    let topic0 = topics.first()?.to_string();
+    match (topic0.as_str(), topics.len()) {
+        // ERC-20 transfers have a non-indexed amount
+        (TRANSFER_TOPIC, 3) => {
+            let amount = U256::abi_decode(&data, false).ok()?;
+            Some(vec![transfer(
+                "erc20",
+                &topics[1],
+                &topics[2],
+                0,
+                Some(amount),
+                None,
+            )])
+        }
+        // ERC-721 transfers have an indexed token id
+        (TRANSFER_TOPIC, 4) => Some(vec![transfer(
+            "erc721",
+            &topics[1],
+            &topics[2],
+            0,
+            None,
+            Some(topics[3].into()),
+        )]),
+        (TRANSFER_SINGLE_TOPIC, 4) => {
+            let (token_id, amount) = <(U256, U256)>::abi_decode_params(&data, false).ok()?;
+            Some(vec![transfer(
+                "erc1155",
+                &topics[2],
+                &topics[3],
+                0,
+                Some(amount),
+                Some(token_id),
+            )])
+        }
+        (TRANSFER_BATCH_TOPIC, 4) => {
+            let (token_ids, amounts) =
+                <(Vec<U256>, Vec<U256>)>::abi_decode_params(&data, false).ok()?;
+            Some(
+                token_ids
+                    .into_iter()
+                    .zip(amounts)
+                    .enumerate()
+                    .map(|(batch_index, (token_id, amount))| {
+                        transfer(
+                            "erc1155",
+                            &topics[2],
+                            &topics[3],
+                            batch_index as i32,
+                            Some(amount),
+                            Some(token_id),
+                        )
+                    })
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+// scans the logs for ERC-20, ERC-721 and ERC-1155 transfer events,
+// logs which do not match the standard event layouts are ignored
+pub fn extract_token_transfers(logs: &[TransactionLog]) -> Vec<TokenTransfer> {
+    logs.iter()
+        .filter(|log| !log.removed)
+        .filter_map(transfers_of_log)
+        .flatten()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "0xea58fcA6849d79EAd1f26608855c2D6407d54Ce2";
+    const FROM_TOPIC: &str = "0x0000000000000000000000005520a8a1723fdc8a8e64da1f348cc1991c13c1c3";
+    const TO_TOPIC: &str = "0x000000000000000000000000ea58fca6849d79ead1f26608855c2d6407d54ce2";
+
+    fn fixture_log(topics: Vec<&str>, data: &str) -> TransactionLog {
+        TransactionLog {
+            transaction_hash: "hash1".to_string(),
+            log_index: 3,
+            address: TOKEN.to_string(),
+            topics: topics.into_iter().map(|topic| topic.to_string()).collect(),
+            data: data.to_string(),
+            removed: false,
+            decoded: None,
+            signature: None,
+        }
+    }
+
+    #[test]
+    fn test_extract_erc20_and_erc721_transfers() {
+        let erc20_log = fixture_log(
+            vec![TRANSFER_TOPIC, FROM_TOPIC, TO_TOPIC],
+            "0x00000000000000000000000000000000000000000000000000000000000003e8",
+        );
+        let erc721_log = fixture_log(
+            vec![
+                TRANSFER_TOPIC,
+                FROM_TOPIC,
+                TO_TOPIC,
+                "0x0000000000000000000000000000000000000000000000000000000000000007",
+            ],
+            "0x",
+        );
+
+        let transfers = extract_token_transfers(&[erc20_log, erc721_log]);
+
+        assert_eq!(transfers.len(), 2);
+        assert_eq!(transfers[0].standard, "erc20");
+        assert_eq!(transfers[0].token, TOKEN);
+        assert_eq!(
+            transfers[0].from,
+            "0x5520a8a1723Fdc8a8e64Da1f348CC1991C13C1C3"
+        );
+        assert_eq!(transfers[0].amount, Some("1000".to_string()));
+        assert_eq!(transfers[0].token_id, None);
+        assert_eq!(transfers[1].standard, "erc721");
+        assert_eq!(transfers[1].amount, None);
+        assert_eq!(transfers[1].token_id, Some("7".to_string()));
+    }
+
+    #[test]
+    fn test_extract_erc1155_transfers() {
+        let operator_topic = FROM_TOPIC;
+        let single_data = format!(
+            "0x{}",
+            hex::encode((U256::from(5), U256::from(10)).abi_encode_params())
+        );
+        let batch_data = format!(
+            "0x{}",
+            hex::encode(
+                (
+                    vec![U256::from(1), U256::from(2)],
+                    vec![U256::from(100), U256::from(200)]
+                )
+                    .abi_encode_params()
+            )
+        );
+
+        let transfers = extract_token_transfers(&[
+            fixture_log(
+                vec![TRANSFER_SINGLE_TOPIC, operator_topic, FROM_TOPIC, TO_TOPIC],
+                &single_data,
+            ),
+            fixture_log(
+                vec![TRANSFER_BATCH_TOPIC, operator_topic, FROM_TOPIC, TO_TOPIC],
+                &batch_data,
+            ),
+        ]);
+
+        assert_eq!(
+            transfers
+                .iter()
+                .map(|transfer| (
+                    transfer.batch_index,
+                    transfer.token_id.clone().unwrap(),
+                    transfer.amount.clone().unwrap()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, "5".to_string(), "10".to_string()),
+                (0, "1".to_string(), "100".to_string()),
+                (1, "2".to_string(), "200".to_string()),
+            ]
+        );
+        assert!(transfers
+            .iter()
+            .all(|transfer| transfer.standard == "erc1155"));
+    }
+
+    #[test]
+    fn test_extract_ignores_other_and_removed_logs() {
+        let mut removed_log = fixture_log(
+            vec![TRANSFER_TOPIC, FROM_TOPIC, TO_TOPIC],
+            "0x00000000000000000000000000000000000000000000000000000000000003e8",
+        );
+        removed_log.removed = true;
+        let other_log = fixture_log(vec![FROM_TOPIC], "0x");
+
+        assert!(extract_token_transfers(&[removed_log, other_log]).is_empty());
+    }
+}
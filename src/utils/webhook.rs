@@ -0,0 +1,627 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+
+use alloy::providers::Provider;
+use futures::{stream, StreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::CONTENT_TYPE,
+    redirect::Policy,
+    Client, Url,
+};
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::{Pool, Postgres};
+
+use crate::config::{
+    AppState, FETCH_CONCURRENCY, WEBHOOK_CHECK_INTERVAL_SECS, WEBHOOK_DELIVERY_INTERVAL_SECS,
+    WEBHOOK_MAX_ATTEMPTS, WEBHOOK_RETRY_BASE_DELAY_SECS, WEBHOOK_RETRY_MAX_DELAY_SECS,
+    WEBHOOK_TIMEOUT_MS,
+};
+
+use super::{
+    db::{
+        watchlist::fetch_address_watchers,
+        webhook::{
+            enqueue_webhook_event, fetch_due_deliveries, fetch_unconfirmed_tracked_transactions,
+            record_delivery_attempt, update_tracked_transaction,
+        },
+    },
+    structs::{
+        chain::ChainError,
+        transaction::ResultTransaction,
+        webhook::{DueDelivery, TrackedTransaction, WebhookBody, WebhookEvent},
+    },
+    transaction::{request_deadline, resolve_transactions},
+};
+
+// max number of due deliveries sent per look into the outbox
+const DELIVERY_BATCH_SIZE: i64 = 100;
+
+// the hex encoded HMAC-SHA256 of the body, keyed with the webhook secret
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+pub fn generate_webhook_secret() -> String {
+    hex::encode(rand::random::<[u8; 32]>())
+}
+
+// false for the private, loopback, link-local and other special purpose addresses, webhooks
+// are only delivered to public addresses so they can not reach internal services
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || first == 0
+                // shared address space of carrier-grade NAT, 100.64.0.0/10
+                || (first == 100 && (64..128).contains(&second)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+// resolves a webhook host, failing when any of its addresses is not public
+async fn resolve_public_addrs(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("Failed to resolve webhook host {}: {}", host, e))?
+        .collect();
+
+    if addrs.is_empty() {
+        return Err(format!("Webhook host {} has no address", host));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        return Err(format!(
+            "Webhook host {} resolves to the non public address {}",
+            host,
+            addr.ip()
+        ));
+    }
+
+    Ok(addrs)
+}
+
+// checks that the url is an http(s) url of a public host, on registration and before
+// every delivery as the host may resolve to other addresses by then
+pub async fn check_webhook_url(url: &str) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|e| format!("Invalid webhook url: {}", e))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(format!(
+            "Webhook url scheme {} is not http(s)",
+            url.scheme()
+        ));
+    }
+
+    let Some(host) = url.host_str() else {
+        return Err("Webhook url has no host".to_string());
+    };
+    // ipv6 hosts are written in brackets
+    let ip: IpAddr = match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) => ip,
+        Err(_) => {
+            let port = url.port_or_known_default().unwrap_or_default();
+            return resolve_public_addrs(host, port).await.map(|_| ());
+        }
+    };
+
+    if !is_public_ip(ip) {
+        return Err(format!("Webhook url address {} is not public", ip));
+    }
+
+    Ok(())
+}
+
+// the resolver of the delivery client, connects to public addresses only so a host rebound
+// to an internal address after the url check is not reached
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            // the port is replaced by the one of the url
+            let addrs = resolve_public_addrs(name.as_str(), 0).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+// seconds before the retry following the given number of attempts
+pub fn webhook_retry_delay(attempts: i32) -> i64 {
+    let exponent = (attempts - 1).clamp(0, 30) as u32;
+    WEBHOOK_RETRY_BASE_DELAY_SECS
+        .saturating_mul(2_i64.pow(exponent))
+        .min(*WEBHOOK_RETRY_MAX_DELAY_SECS)
+}
+
+pub fn confirmations(block_number: i32, head: u64) -> u64 {
+    (head + 1).saturating_sub(block_number as u64)
+}
+
+// moves the tracked trx to the block it is now seen in (None while pending or once dropped),
+// returns the events of the move
+pub fn track_transaction(
+    tracked: &mut TrackedTransaction,
+    block: Option<(i32, String)>,
+    head: u64,
+) -> Vec<WebhookEvent> {
+    let mut events = vec![];
+    let block_hash = block.as_ref().map(|(_, block_hash)| block_hash);
+
+    if tracked.block_hash.is_some() && tracked.block_hash.as_ref() != block_hash {
+        events.push(WebhookEvent::TransactionReorged);
+    }
+    if block_hash.is_some() && tracked.block_hash.as_ref() != block_hash {
+        events.push(WebhookEvent::TransactionMined);
+    }
+
+    tracked.block_number = block.as_ref().map(|(block_number, _)| *block_number);
+    tracked.block_hash = block.map(|(_, block_hash)| block_hash);
+
+    if let Some(block_number) = tracked.block_number {
+        if confirmations(block_number, head) >= tracked.confirmations as u64 {
+            tracked.confirmed = true;
+            events.push(WebhookEvent::TransactionConfirmed);
+        }
+    }
+
+    events
+}
+
+// enqueues the events of the tracked trxs which were mined, confirmed or reorged since the last check
+pub async fn check_tracked_transactions(state: &AppState) -> Result<(), String> {
+    let tracked_transactions =
+        fetch_unconfirmed_tracked_transactions(&state.db_connection, state.chain_id())
+            .await
+            .map_err(|e| format!("Failed to fetch tracked trxs: {}", e))?;
+    if tracked_transactions.is_empty() {
+        return Ok(());
+    }
+
+    let head = state
+        .eth_client
+        .get_block_number()
+        .await
+        .map_err(|e| format!("Failed to fetch latest block number: {}", e))?;
+
+    let mut transaction_hashes: Vec<String> = tracked_transactions
+        .iter()
+        .map(|tracked| tracked.transaction_hash.clone())
+        .collect();
+    transaction_hashes.sort();
+    transaction_hashes.dedup();
+    let resolved: HashMap<String, Result<ResultTransaction, ChainError>> =
+        resolve_transactions(state, transaction_hashes, request_deadline())
+            .await
+            .into_iter()
+            .collect();
+
+    for mut tracked in tracked_transactions {
+        let trx = match resolved.get(&tracked.transaction_hash) {
+            Some(Ok(trx)) => Some(trx),
+            // a trx unknown to the node was dropped or replaced
+            Some(Err(ChainError::NotFound(_))) => None,
+            // the trxs which could not be resolved are checked again next time
+            _ => continue,
+        };
+        let previous = tracked.clone();
+        let block = trx.and_then(|trx| trx.block_number.zip(trx.block_hash.clone()));
+
+        for event in track_transaction(&mut tracked, block, head) {
+            let (block_number, block_hash) = match event {
+                WebhookEvent::TransactionReorged => (previous.block_number, &previous.block_hash),
+                _ => (tracked.block_number, &tracked.block_hash),
+            };
+            let event_key = format!(
+                "{}:{}:{}",
+                event.as_str(),
+                tracked.transaction_hash,
+                block_hash.clone().unwrap_or_default()
+            );
+            let payload = json!({
+                "transactionHash": tracked.transaction_hash,
+                "blockNumber": block_number,
+                "blockHash": block_hash,
+                "confirmations": block_number.map(|block_number| confirmations(block_number, head)),
+                "transaction": trx,
+            });
+
+            enqueue_webhook_event(
+                &state.db_connection,
+                state.chain_id(),
+                &tracked.user_name,
+                event,
+                &event_key,
+                &payload,
+            )
+            .await
+            .map_err(|e| format!("Failed to enqueue webhook event: {}", e))?;
+        }
+
+        if tracked != previous {
+            update_tracked_transaction(&state.db_connection, state.chain_id(), &tracked)
+                .await
+                .map_err(|e| format!("Failed to update tracked trx: {}", e))?;
+        }
+    }
+
+    Ok(())
+}
+
+// enqueues an address.seen event for every user watching the sender or receiver of the trx
+pub async fn notify_address_watchers(
+    state: &AppState,
+    trx: &ResultTransaction,
+) -> Result<(), String> {
+    let addresses: Vec<String> = std::iter::once(&trx.from)
+        .chain(trx.to.as_ref())
+        .chain(trx.contract_address.as_ref())
+        .map(|address| address.to_lowercase())
+        .collect();
+    let watchers = fetch_address_watchers(&state.db_connection, state.chain_id(), &addresses)
+        .await
+        .map_err(|e| format!("Failed to fetch address watchers: {}", e))?;
+
+    for (user_name, address) in watchers {
+        let event_key = format!(
+            "{}:{}:{}:{}",
+            WebhookEvent::AddressSeen.as_str(),
+            address,
+            trx.transaction_hash,
+            trx.block_hash.clone().unwrap_or_default()
+        );
+
+        enqueue_webhook_event(
+            &state.db_connection,
+            state.chain_id(),
+            &user_name,
+            WebhookEvent::AddressSeen,
+            &event_key,
+            &json!({ "address": address, "transaction": trx }),
+        )
+        .await
+        .map_err(|e| format!("Failed to enqueue webhook event: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// posts the signed event to the webhook url, returns the http status and the error of a
+// failed attempt, any non 2xx answer is a failure
+pub async fn send_delivery(
+    client: &Client,
+    delivery: &DueDelivery,
+) -> (Option<i32>, Option<String>) {
+    let body = WebhookBody {
+        id: delivery.id,
+        event: delivery.event.clone(),
+        chain_id: delivery.chain_id,
+        created_at: delivery.created_at,
+        data: delivery.payload.0.clone(),
+    };
+    let body = match serde_json::to_vec(&body) {
+        Ok(body) => body,
+        Err(e) => return (None, Some(format!("Failed to encode webhook body: {}", e))),
+    };
+
+    let response = client
+        .post(&delivery.url)
+        .header(CONTENT_TYPE, "application/json")
+        .header("x-webhook-event", &delivery.event)
+        .header("x-webhook-delivery", delivery.id.to_string())
+        .header(
+            "x-webhook-signature",
+            format!("sha256={}", sign_payload(&delivery.secret, &body)),
+        )
+        .body(body)
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status().is_success() => {
+            (Some(response.status().as_u16() as i32), None)
+        }
+        Ok(response) => (
+            Some(response.status().as_u16() as i32),
+            Some(format!("Webhook answered {}", response.status())),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+// sends the due deliveries of the outbox, a failed delivery is retried with backoff
+// until WEBHOOK_MAX_ATTEMPTS attempts failed
+pub async fn deliver_due_webhooks(
+    db_connection: &Pool<Postgres>,
+    client: &Client,
+) -> Result<(), String> {
+    let due = fetch_due_deliveries(db_connection, DELIVERY_BATCH_SIZE)
+        .await
+        .map_err(|e| format!("Failed to fetch due deliveries: {}", e))?;
+
+    stream::iter(due)
+        .for_each_concurrent(*FETCH_CONCURRENCY, |delivery| async move {
+            let (status_code, error) = match check_webhook_url(&delivery.url).await {
+                Ok(()) => send_delivery(client, &delivery).await,
+                Err(error) => (None, Some(error)),
+            };
+            let attempts = delivery.attempts + 1;
+            let retry_in_secs = (error.is_some() && attempts < *WEBHOOK_MAX_ATTEMPTS)
+                .then(|| webhook_retry_delay(attempts));
+
+            if let Err(e) = record_delivery_attempt(
+                db_connection,
+                delivery.id,
+                status_code,
+                error.as_ref(),
+                retry_in_secs,
+            )
+            .await
+            {
+                println!(
+                    "Failed to record webhook delivery {}, error:{}",
+                    delivery.id, e
+                );
+            }
+        })
+        .await;
+
+    Ok(())
+}
+
+// background task checking the tracked trxs of the chain every WEBHOOK_CHECK_INTERVAL_SECS
+pub async fn watch_tracked_transactions(state: AppState) {
+    let mut interval = tokio::time::interval(Duration::from_secs(*WEBHOOK_CHECK_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(err_msg) = check_tracked_transactions(&state).await {
+            println!(
+                "Tracked trxs check failed on {}, error:{}",
+                state.chain.name, err_msg
+            );
+        }
+    }
+}
+
+// background task sending the webhook deliveries of all chains
+pub async fn run_webhook_deliveries(db_connection: Pool<Postgres>) {
+    // redirects are not followed, they could point to an internal address
+    let client = Client::builder()
+        .timeout(Duration::from_millis(*WEBHOOK_TIMEOUT_MS))
+        .dns_resolver(Arc::new(PublicResolver))
+        .redirect(Policy::none())
+        .build()
+        .expect("Failed to build webhook client");
+    let mut interval = tokio::time::interval(Duration::from_secs(*WEBHOOK_DELIVERY_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+
+        if let Err(err_msg) = deliver_due_webhooks(&db_connection, &client).await {
+            println!("Webhook deliveries failed, error:{}", err_msg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Bytes,
+        http::{HeaderMap, StatusCode},
+        routing::post,
+        Router,
+    };
+    use serde_json::Value;
+    use std::sync::{Arc, Mutex};
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, Bytes)>>>;
+
+    // a local webhook receiver answering with the status, returns its url and the received posts
+    async fn fixture_receiver(status: StatusCode) -> (String, Received) {
+        let received: Received = Arc::new(Mutex::new(vec![]));
+        let receiver_received = received.clone();
+        let app = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| async move {
+                receiver_received.lock().unwrap().push((headers, body));
+                status
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind test receiver");
+        let address = listener
+            .local_addr()
+            .expect("Failed to read test receiver address");
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        (format!("http://{}/hook", address), received)
+    }
+
+    fn fixture_delivery(url: String) -> DueDelivery {
+        DueDelivery {
+            id: 7,
+            chain_id: 11155111,
+            event: WebhookEvent::TransactionMined.as_str().to_string(),
+            payload: sqlx::types::Json(json!({ "transactionHash": "0x01" })),
+            attempts: 0,
+            created_at: 1700000000,
+            url,
+            secret: "secret".to_string(),
+        }
+    }
+
+    fn fixture_tracked(block: Option<(i32, &str)>) -> TrackedTransaction {
+        TrackedTransaction {
+            user_name: "alice".to_string(),
+            transaction_hash: "0x01".to_string(),
+            confirmations: 3,
+            block_number: block.map(|(block_number, _)| block_number),
+            block_hash: block.map(|(_, block_hash)| block_hash.to_string()),
+            confirmed: false,
+            created_at: 1700000000,
+        }
+    }
+
+    #[test]
+    fn test_sign_payload() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign_payload("Jefe", b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_webhook_retry_delay() {
+        let base = *WEBHOOK_RETRY_BASE_DELAY_SECS;
+
+        assert_eq!(webhook_retry_delay(1), base);
+        assert_eq!(webhook_retry_delay(3), base * 4);
+        assert_eq!(webhook_retry_delay(40), *WEBHOOK_RETRY_MAX_DELAY_SECS);
+    }
+
+    #[test]
+    fn test_track_transaction() {
+        // still pending
+        let mut tracked = fixture_tracked(None);
+        assert!(track_transaction(&mut tracked, None, 100).is_empty());
+
+        // mined, one confirmation
+        assert_eq!(
+            track_transaction(&mut tracked, Some((100, "0xa".to_string())), 100),
+            vec![WebhookEvent::TransactionMined]
+        );
+        assert_eq!(tracked.block_number, Some(100));
+
+        // reorged into another block
+        assert_eq!(
+            track_transaction(&mut tracked, Some((101, "0xb".to_string())), 101),
+            vec![
+                WebhookEvent::TransactionReorged,
+                WebhookEvent::TransactionMined
+            ]
+        );
+
+        // reached its confirmations
+        assert_eq!(
+            track_transaction(&mut tracked, Some((101, "0xb".to_string())), 103),
+            vec![WebhookEvent::TransactionConfirmed]
+        );
+        assert!(tracked.confirmed);
+
+        // dropped from its block
+        let mut tracked = fixture_tracked(Some((100, "0xa")));
+        assert_eq!(
+            track_transaction(&mut tracked, None, 101),
+            vec![WebhookEvent::TransactionReorged]
+        );
+        assert_eq!(tracked.block_hash, None);
+    }
+
+    #[test]
+    fn test_is_public_ip() {
+        for ip in ["1.1.1.1", "93.184.216.34", "2606:4700:4700::1111"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_webhook_url() {
+        assert!(check_webhook_url("https://1.1.1.1/hook").await.is_ok());
+        assert!(check_webhook_url("ftp://1.1.1.1/hook").await.is_err());
+        assert!(check_webhook_url("http://169.254.169.254/latest/meta-data")
+            .await
+            .is_err());
+        assert!(check_webhook_url("http://192.168.1.1/hook").await.is_err());
+        assert!(check_webhook_url("http://[::1]:8080/hook").await.is_err());
+        assert!(check_webhook_url("http://localhost:8080/hook")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_delivery_signs_the_body() {
+        let (url, received) = fixture_receiver(StatusCode::OK).await;
+        let delivery = fixture_delivery(url);
+
+        let (status_code, error) = send_delivery(&Client::new(), &delivery).await;
+        assert_eq!(status_code, Some(200));
+        assert_eq!(error, None);
+
+        let received = received.lock().unwrap();
+        let (headers, body) = &received[0];
+        assert_eq!(
+            headers["x-webhook-signature"],
+            format!("sha256={}", sign_payload("secret", body))
+        );
+        assert_eq!(headers["x-webhook-event"], "transaction.mined");
+        assert_eq!(headers["x-webhook-delivery"], "7");
+
+        let body: Value = serde_json::from_slice(body).expect("Invalid webhook body");
+        assert_eq!(body["id"], 7);
+        assert_eq!(body["chainId"], 11155111);
+        assert_eq!(body["data"]["transactionHash"], "0x01");
+    }
+
+    #[tokio::test]
+    async fn test_send_delivery_fails_on_error_status() {
+        let (url, received) = fixture_receiver(StatusCode::INTERNAL_SERVER_ERROR).await;
+
+        let (status_code, error) = send_delivery(&Client::new(), &fixture_delivery(url)).await;
+        assert_eq!(status_code, Some(500));
+        assert!(error.is_some());
+        assert_eq!(received.lock().unwrap().len(), 1);
+
+        // an unreachable receiver has no status
+        let (status_code, error) = send_delivery(
+            &Client::new(),
+            &fixture_delivery("http://127.0.0.1:1/hook".to_string()),
+        )
+        .await;
+        assert_eq!(status_code, None);
+        assert!(error.is_some());
+    }
+}